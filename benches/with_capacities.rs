@@ -0,0 +1,77 @@
+use beat_saber_map::{
+    beatmap::{ColorNoteData, GridPosition, Object},
+    v3::{BeatmapV3, ColorNoteV3},
+    Beatmap,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+const NOTES: usize = 50_000;
+
+fn beatmap_v3() -> BeatmapV3 {
+    BeatmapV3 {
+        version: "3.3.0".to_string(),
+        color_notes: (0..NOTES)
+            .map(|i| ColorNoteV3 {
+                beat: i as f64 / 4.0,
+                x: (i % 4) as i32,
+                y: (i / 4 % 3) as i32,
+                color: (i % 2) as u8,
+                cut_direction: (i % 9) as i32,
+                angle_offset: 0,
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Pushes the color notes of `beatmap_v3` onto `beatmap` one at a time, the
+/// way a generator would.
+fn push_color_notes(beatmap_v3: &BeatmapV3, mut beatmap: Beatmap) -> Beatmap {
+    for note in &beatmap_v3.color_notes {
+        beatmap.color_notes.push(Object {
+            beat: note.beat,
+            metadata_index: beatmap.color_notes_data.len(),
+            ..Default::default()
+        });
+        beatmap.color_notes_data.push(ColorNoteData {
+            grid_position: GridPosition {
+                line_index: (note.x as u8).try_into().unwrap(),
+                line_layer: (note.y as u8).try_into().unwrap(),
+            },
+            color: note.color.try_into().unwrap(),
+            cut_direction: (note.cut_direction as u8).try_into().unwrap(),
+            angle_offset: note.angle_offset,
+            ..Default::default()
+        });
+    }
+
+    beatmap
+}
+
+fn convert_beatmap(c: &mut Criterion) {
+    let beatmap = beatmap_v3();
+    let mut group = c.benchmark_group("convert 50k note v3 beatmap");
+
+    group.bench_function("from", |b| {
+        b.iter_batched(
+            || beatmap.clone(),
+            |beatmap| Beatmap::from(black_box(beatmap)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("with capacities", |b| {
+        b.iter(|| {
+            push_color_notes(
+                black_box(&beatmap),
+                Beatmap::with_capacities(NOTES, 0, 0, 0, 0),
+            )
+        })
+    });
+    group.bench_function("without capacities", |b| {
+        b.iter(|| push_color_notes(black_box(&beatmap), Beatmap::default()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, convert_beatmap);
+criterion_main!(benches);
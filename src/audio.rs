@@ -2,11 +2,19 @@
 //!
 //! See [`Audio`] to get started.
 
-use std::{fs, path::Path};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{Beats, Error};
+use crate::{parse_file, Beats, Error};
+
+/// Sample rates (in Hz) commonly used by audio files, accepted by
+/// [`Audio::validate_frequency`].
+pub const STANDARD_SAMPLE_RATES: &[u32] = &[22050, 32000, 44100, 48000, 88200, 96000];
 
 /// Information regarding how an audio file should be processed.
 #[doc = bsmg_wiki!("audio")]
@@ -45,11 +53,260 @@ impl Default for Audio {
 }
 
 impl Audio {
+    /// Instantiates an [`Audio`] for a song with a constant BPM, with a single
+    /// region in both [`Audio::bpm_data`] and [`Audio::lufs_data`] spanning
+    /// the whole song.
+    ///
+    /// The last beat is derived from the duration of the song, so it is `0.0`
+    /// if `frequency` is `0`.
+    pub fn from_constant_bpm(bpm: Beats, sample_count: u32, frequency: u32) -> Self {
+        let end_index = sample_count as usize;
+        let end_beat = if frequency == 0 {
+            0.0
+        } else {
+            sample_count as f64 / frequency as f64 * bpm / 60.0
+        };
+
+        Self {
+            song_sample_count: sample_count,
+            song_frequency: frequency,
+            bpm_data: vec![BpmData {
+                start_index: 0,
+                end_index,
+                start_beat: 0.0,
+                end_beat,
+            }],
+            lufs_data: vec![LufsData {
+                start_index: 0,
+                end_index,
+                loudness: 0.0,
+            }],
+            ..Default::default()
+        }
+    }
+
     /// Instantiates an [`Audio`] from an audio file, typically named
     /// `BPMInfo.dat`.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
-        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        parse_file(path.as_ref(), Self::from_slice)
+    }
+
+    /// Instantiates an [`Audio`] from the contents of an audio file, such as
+    /// data received over the network.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Serializes this [`Audio`] to a file, typically named `BPMInfo.dat`.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer_pretty(&mut writer, self)?;
+
+        Ok(writer.flush()?)
+    }
+
+    /// Checks that [`Audio::song_frequency`] is one of
+    /// [`STANDARD_SAMPLE_RATES`], since a wrong frequency throws off the timing
+    /// of the whole map.
+    ///
+    /// See [`Audio::validate_frequency_with`] to accept other sample rates.
+    pub fn validate_frequency(&self) -> Option<FrequencyWarning> {
+        self.validate_frequency_with(STANDARD_SAMPLE_RATES)
+    }
+
+    /// Checks that [`Audio::song_frequency`] is one of `accepted_rates`.
+    pub fn validate_frequency_with(&self, accepted_rates: &[u32]) -> Option<FrequencyWarning> {
+        (!accepted_rates.contains(&self.song_frequency)).then_some(FrequencyWarning {
+            frequency: self.song_frequency,
+        })
+    }
+
+    /// Checks that the regions of [`Audio::bpm_data`] are in order and
+    /// contiguous, since anything else desyncs the map in-game.
+    ///
+    /// Each region must end after it starts, in both samples and beats, and
+    /// start on the sample the previous region ends on, and no earlier than
+    /// the beat it ends on.
+    ///
+    /// Returns [`Error::InvalidBpmRegion`] for the first offending region.
+    pub fn validate_bpm_regions(&self) -> Result<(), Error> {
+        for (index, region) in self.bpm_data.iter().enumerate() {
+            let invalid = |problem| Err(Error::InvalidBpmRegion { index, problem });
+
+            if region.start_index >= region.end_index || region.start_beat >= region.end_beat {
+                return invalid(BpmRegionProblem::Empty);
+            }
+
+            let Some(previous) = index.checked_sub(1).map(|i| &self.bpm_data[i]) else {
+                continue;
+            };
+
+            if region.start_index != previous.end_index {
+                return invalid(BpmRegionProblem::NotContiguous);
+            }
+
+            if region.start_beat < previous.end_beat {
+                return invalid(BpmRegionProblem::BeatsDecrease);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the sample index of the audio file that a beat falls on.
+    ///
+    /// The position is linearly interpolated within the region of
+    /// [`Audio::bpm_data`] containing `beat`. Beats outside of every region are
+    /// clamped to the start of the first or end of the last region, and `0` is
+    /// returned if there are no regions.
+    ///
+    /// See [`Audio::sample_to_beat`] for the inverse.
+    pub fn beat_to_sample(&self, beat: Beats) -> usize {
+        let Some(region) = self.region_by(beat, |region| (region.start_beat, region.end_beat))
+        else {
+            return 0;
+        };
+        let beat = beat.max(region.start_beat).min(region.end_beat);
+        let beats = region.end_beat - region.start_beat;
+
+        if beats == 0.0 {
+            return region.start_index;
+        }
+
+        let samples = region.end_index as f64 - region.start_index as f64;
+        let progress = (beat - region.start_beat) / beats;
+
+        (region.start_index as f64 + progress * samples).round() as usize
+    }
+
+    /// Finds the beat that a sample index of the audio file falls on, the
+    /// inverse of [`Audio::beat_to_sample`].
+    ///
+    /// Samples outside of every region are clamped to the start of the first
+    /// or end of the last region, and `0.0` is returned if there are no
+    /// regions.
+    pub fn sample_to_beat(&self, sample: usize) -> Beats {
+        let Some(region) = self.region_by(sample as f64, |region| {
+            (region.start_index as f64, region.end_index as f64)
+        }) else {
+            return 0.0;
+        };
+        let sample = sample.clamp(region.start_index, region.end_index.max(region.start_index));
+        let samples = region.end_index as f64 - region.start_index as f64;
+
+        if samples == 0.0 {
+            return region.start_beat;
+        }
+
+        let beats = region.end_beat - region.start_beat;
+        let progress = (sample - region.start_index) as f64 / samples;
+
+        region.start_beat + progress * beats
+    }
+
+    /// Converts a beat into the time (in seconds) of the audio file it falls
+    /// on, accounting for every BPM change in [`Audio::bpm_data`].
+    ///
+    /// The time is linearly interpolated within the region containing `beat`,
+    /// and extrapolated from the slope of the first or last region for beats
+    /// outside of every region. Returns [`f64::NAN`] if there are no regions or
+    /// [`Audio::song_frequency`] is `0`.
+    ///
+    /// See [`Audio::seconds_to_beat`] for the inverse.
+    pub fn beat_to_seconds(&self, beat: Beats) -> f64 {
+        let Some(region) = self.region_by(beat, |region| (region.start_beat, region.end_beat))
+        else {
+            return f64::NAN;
+        };
+
+        if self.song_frequency == 0 {
+            return f64::NAN;
+        }
+
+        let beats = region.end_beat - region.start_beat;
+        let samples = region.end_index as f64 - region.start_index as f64;
+        let sample = if beats == 0.0 {
+            region.start_index as f64
+        } else {
+            region.start_index as f64 + (beat - region.start_beat) * samples / beats
+        };
+
+        sample / self.song_frequency as f64
+    }
+
+    /// Converts a time (in seconds) of the audio file into the beat it falls
+    /// on, the inverse of [`Audio::beat_to_seconds`].
+    ///
+    /// Returns [`f64::NAN`] if there are no regions or [`Audio::song_frequency`]
+    /// is `0`.
+    pub fn seconds_to_beat(&self, seconds: f64) -> Beats {
+        let sample = seconds * self.song_frequency as f64;
+        let Some(region) = self.region_by(sample, |region| {
+            (region.start_index as f64, region.end_index as f64)
+        }) else {
+            return f64::NAN;
+        };
+
+        if self.song_frequency == 0 {
+            return f64::NAN;
+        }
+
+        let beats = region.end_beat - region.start_beat;
+        let samples = region.end_index as f64 - region.start_index as f64;
+
+        if samples == 0.0 {
+            region.start_beat
+        } else {
+            region.start_beat + (sample - region.start_index as f64) * beats / samples
+        }
     }
+
+    /// Finds the region of [`Audio::bpm_data`] whose range, as given by
+    /// `range`, contains `position`, or the first or last region if `position`
+    /// is before or after every region.
+    fn region_by(&self, position: f64, range: impl Fn(&BpmData) -> (f64, f64)) -> Option<&BpmData> {
+        let first = self.bpm_data.first()?;
+
+        self.bpm_data
+            .iter()
+            .find(|region| {
+                let (start, end) = range(region);
+
+                (start..=end).contains(&position)
+            })
+            .or_else(|| {
+                if position < range(first).0 {
+                    Some(first)
+                } else {
+                    self.bpm_data.last()
+                }
+            })
+    }
+}
+
+/// An [`Audio::song_frequency`] which is not an accepted sample rate, see
+/// [`Audio::validate_frequency`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Song frequency of {frequency} Hz is not a standard sample rate")]
+pub struct FrequencyWarning {
+    /// The unusual frequency.
+    pub frequency: u32,
+}
+
+/// Why a region of [`Audio::bpm_data`] is invalid, see
+/// [`Audio::validate_bpm_regions`].
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BpmRegionProblem {
+    /// The region does not end after it starts, in samples or beats.
+    #[error("it does not end after it starts")]
+    Empty,
+    /// The region does not start on the sample the previous region ends on.
+    #[error("it does not start where the previous region ends")]
+    NotContiguous,
+    /// The region starts on an earlier beat than the previous region ends on.
+    #[error("it starts before the beat the previous region ends on")]
+    BeatsDecrease,
 }
 
 /// Regions in an [`Audio`] to alter the BPM of.
@@ -74,7 +331,7 @@ pub struct BpmData {
 /// Normalization to apply to the loudness of an [`Audio`] within the specified
 /// region.
 #[doc = bsmg_wiki!("audio"#"lufs-data")]
-#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
 #[serde(default)]
 pub struct LufsData {
     /// The starting sample index.
@@ -83,9 +340,9 @@ pub struct LufsData {
     /// The ending sample index.
     #[serde(rename = "ei")]
     pub end_index: usize,
-    /// The loudness.
+    /// The loudness (in LUFS).
     #[serde(rename = "l")]
-    pub loudness: usize,
+    pub loudness: f64,
 }
 
 #[cfg(test)]
@@ -113,7 +370,7 @@ mod tests {
             lufs_data: vec![LufsData {
                 start_index: 0,
                 end_index: 1149214,
-                loudness: 0,
+                loudness: 0.0,
             }],
         }
     }
@@ -131,4 +388,162 @@ mod tests {
 
         assert_eq!(deserialized, manual_recreation());
     }
+
+    #[test]
+    fn writes_default_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BPMInfo.dat");
+
+        Audio::default().to_file(&path).unwrap();
+
+        assert_eq!(Audio::from_file(&path).unwrap(), Audio::default());
+    }
+
+    #[test]
+    fn validates_frequency() {
+        let mut audio = manual_recreation();
+
+        assert_eq!(audio.validate_frequency(), None);
+
+        audio.song_frequency = 4410;
+
+        assert_eq!(
+            audio.validate_frequency(),
+            Some(FrequencyWarning { frequency: 4410 })
+        );
+        assert_eq!(audio.validate_frequency_with(&[4410]), None);
+    }
+
+    #[test]
+    fn converts_beat_to_sample() {
+        let audio = manual_recreation();
+
+        assert_eq!(audio.beat_to_sample(0.0), 0);
+        assert_eq!(audio.beat_to_sample(13.0), 574607);
+        assert_eq!(audio.beat_to_sample(26.0), 1149214);
+        assert_eq!(audio.beat_to_sample(27.0), 1149214);
+        assert_eq!(audio.beat_to_sample(-1.0), 0);
+        assert_eq!(Audio::default().beat_to_sample(13.0), 0);
+
+        let mut audio = manual_recreation();
+
+        audio.bpm_data[0].start_beat = 30.0;
+        audio.beat_to_sample(13.0);
+        audio.bpm_data[0].start_beat = f64::NAN;
+        audio.beat_to_sample(13.0);
+    }
+
+    #[test]
+    fn converts_sample_to_beat() {
+        let mut audio = manual_recreation();
+
+        audio.bpm_data.push(BpmData {
+            start_index: 1149214,
+            end_index: 1200000,
+            start_beat: 26.0,
+            end_beat: 30.0,
+        });
+
+        assert_eq!(audio.sample_to_beat(0), 0.0);
+        assert_eq!(audio.sample_to_beat(574607), 13.0);
+        assert_eq!(audio.sample_to_beat(1174607), 28.0);
+        assert_eq!(audio.sample_to_beat(2000000), 30.0);
+        assert_eq!(audio.beat_to_sample(28.0), 1174607);
+        assert_eq!(Audio::default().sample_to_beat(574607), 0.0);
+    }
+
+    #[test]
+    fn converts_between_beats_and_seconds() {
+        let mut audio = manual_recreation();
+
+        audio.bpm_data = vec![
+            BpmData {
+                start_index: 0,
+                end_index: 441000,
+                start_beat: 0.0,
+                end_beat: 20.0,
+            },
+            BpmData {
+                start_index: 441000,
+                end_index: 882000,
+                start_beat: 20.0,
+                end_beat: 60.0,
+            },
+        ];
+
+        assert_eq!(audio.beat_to_seconds(10.0), 5.0);
+        assert_eq!(audio.beat_to_seconds(40.0), 15.0);
+        assert_eq!(audio.beat_to_seconds(80.0), 25.0);
+        assert_eq!(audio.seconds_to_beat(5.0), 10.0);
+        assert_eq!(audio.seconds_to_beat(15.0), 40.0);
+        assert_eq!(audio.seconds_to_beat(25.0), 80.0);
+        assert!(Audio::default().beat_to_seconds(1.0).is_nan());
+    }
+
+    #[test]
+    fn deserializes_fractional_loudness() {
+        let lufs_data: LufsData =
+            serde_json::from_str(r#"{ "si": 0, "ei": 1149214, "l": -13.5 }"#).unwrap();
+
+        assert_eq!(lufs_data.loudness, -13.5);
+    }
+
+    #[test]
+    fn creates_from_constant_bpm() {
+        let audio = Audio::from_constant_bpm(120.0, 44100 * 30, 44100);
+
+        assert_eq!(audio.bpm_data.len(), 1);
+        assert_eq!(audio.bpm_data[0].end_index, 44100 * 30);
+        assert_eq!(audio.bpm_data[0].end_beat, 60.0);
+        assert_eq!(audio.lufs_data[0].end_index, 44100 * 30);
+        assert!(audio.validate_bpm_regions().is_ok());
+        assert_eq!(audio.beat_to_seconds(60.0), 30.0);
+        assert_eq!(
+            Audio::from_constant_bpm(120.0, 44100, 0).bpm_data[0].end_beat,
+            0.0
+        );
+    }
+
+    #[test]
+    fn validates_bpm_regions() {
+        let mut audio = manual_recreation();
+
+        assert!(audio.validate_bpm_regions().is_ok());
+
+        audio.bpm_data.push(BpmData {
+            start_index: 1149215,
+            end_index: 1200000,
+            start_beat: 26.0,
+            end_beat: 28.0,
+        });
+
+        assert!(matches!(
+            audio.validate_bpm_regions(),
+            Err(Error::InvalidBpmRegion {
+                index: 1,
+                problem: BpmRegionProblem::NotContiguous,
+            })
+        ));
+
+        audio.bpm_data[1].start_index = 1149214;
+        audio.bpm_data[1].start_beat = 25.0;
+
+        assert!(matches!(
+            audio.validate_bpm_regions(),
+            Err(Error::InvalidBpmRegion {
+                index: 1,
+                problem: BpmRegionProblem::BeatsDecrease,
+            })
+        ));
+
+        audio.bpm_data[0].end_index = 0;
+
+        assert!(matches!(
+            audio.validate_bpm_regions(),
+            Err(Error::InvalidBpmRegion {
+                index: 0,
+                problem: BpmRegionProblem::Empty,
+            })
+        ));
+    }
 }
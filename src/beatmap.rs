@@ -4,11 +4,83 @@
 
 #![allow(deprecated)]
 
-use std::{fs, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Range,
+    path::Path,
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use serde_json::json;
 
-use crate::{Beats, Error};
+use crate::{
+    info::{half_jump_duration, Audio},
+    lenient::LenientU8,
+    parse_file,
+    v1::BeatmapV1,
+    v3::BeatmapV3,
+    Beats, Error,
+};
+
+/// Implements [`TryFrom<LenientU8>`] for enums which implement
+/// [`TryFrom<u8>`], so that they can be deserialized from whole-number floats.
+macro_rules! impl_try_from_lenient_u8 {
+    ($($enum:ty),+ $(,)?) => {
+        $(
+            impl TryFrom<LenientU8> for $enum {
+                type Error = crate::Error;
+
+                fn try_from(value: LenientU8) -> Result<Self, Self::Error> {
+                    Self::try_from(value.0)
+                }
+            }
+        )+
+    };
+}
+
+impl_try_from_lenient_u8!(
+    LineIndex,
+    LineLayer,
+    Color,
+    CutDirection,
+    MidAnchorMode,
+    ExecutionTime,
+);
+
+/// Adds inherent `from_u8` and `as_u8` methods to enums which implement
+/// [`TryFrom<u8>`] and [`From<Self>`] for [`u8`], so that they can be converted
+/// without importing either trait.
+macro_rules! impl_u8_methods {
+    ($($enum:ident),+ $(,)?) => {
+        $(
+            impl $enum {
+                #[doc = concat!("Converts a raw integer into a [`", stringify!($enum), "`].")]
+                pub fn from_u8(value: u8) -> Result<Self, Error> {
+                    Self::try_from(value)
+                }
+
+                #[doc = concat!("Converts this [`", stringify!($enum), "`] into a raw integer.")]
+                pub fn as_u8(self) -> u8 {
+                    self.into()
+                }
+            }
+        )+
+    };
+}
+
+impl_u8_methods!(LineIndex, LineLayer, Color, CutDirection, MidAnchorMode);
+
+/// How far apart two beats may be while still being considered simultaneous.
+const SIMULTANEOUS_EPSILON: Beats = 0.001;
+
+/// How far apart two notes of the same color may be while still being cut in
+/// the same swing.
+const SWING_WINDOW: Beats = 0.125;
+
+/// How long after a note a bomb may come while still resetting the swing.
+const RESET_WINDOW: Beats = 1.0;
 
 /// Collections and associated metadata for all *interactable* beatmap items,
 /// such as notes and obstacles.
@@ -45,6 +117,35 @@ pub struct Beatmap {
     /// See [`SpawnRotationData`].
     #[deprecated = "use `beatmap::Object::rotation_lane` instead"]
     pub spawn_rotations_data: Vec<SpawnRotationData>,
+    /// Additional data used by mods, such as Noodle Extensions' custom
+    /// events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_data: Option<serde_json::Value>,
+}
+
+/// The fields of [`Beatmap`], which fails to deserialize if there are any
+/// others, see [`Beatmap::from_str_strict`].
+///
+/// Must list every field of [`Beatmap`], which the
+/// `strictly_accepts_every_field` test checks.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+#[allow(dead_code)]
+struct StrictBeatmap {
+    version: IgnoredAny,
+    color_notes: IgnoredAny,
+    color_notes_data: IgnoredAny,
+    bomb_notes: IgnoredAny,
+    bomb_notes_data: IgnoredAny,
+    obstacles: IgnoredAny,
+    obstacles_data: IgnoredAny,
+    arcs: IgnoredAny,
+    arcs_data: IgnoredAny,
+    chains: IgnoredAny,
+    chains_data: IgnoredAny,
+    spawn_rotations: IgnoredAny,
+    spawn_rotations_data: IgnoredAny,
+    custom_data: IgnoredAny,
 }
 
 impl Default for Beatmap {
@@ -63,14 +164,1483 @@ impl Default for Beatmap {
             chains_data: Default::default(),
             spawn_rotations: Default::default(),
             spawn_rotations_data: Default::default(),
+            custom_data: Default::default(),
         }
     }
 }
 
 impl Beatmap {
+    /// Instantiates an empty [`Beatmap`] with room for the given number of
+    /// each kind of object, and their data, without reallocating.
+    ///
+    /// Useful when generating beatmaps whose size is known up front.
+    pub fn with_capacities(
+        color_notes: usize,
+        bomb_notes: usize,
+        obstacles: usize,
+        arcs: usize,
+        chains: usize,
+    ) -> Self {
+        Self {
+            color_notes: Vec::with_capacity(color_notes),
+            color_notes_data: Vec::with_capacity(color_notes),
+            bomb_notes: Vec::with_capacity(bomb_notes),
+            bomb_notes_data: Vec::with_capacity(bomb_notes),
+            obstacles: Vec::with_capacity(obstacles),
+            obstacles_data: Vec::with_capacity(obstacles),
+            arcs: Vec::with_capacity(arcs),
+            arcs_data: Vec::with_capacity(arcs),
+            chains: Vec::with_capacity(chains),
+            chains_data: Vec::with_capacity(chains),
+            ..Default::default()
+        }
+    }
+
     /// Instantiates a [`Beatmap`] from a beatmap file.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
-        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        parse_file(path.as_ref(), Self::from_slice)
+    }
+
+    /// Instantiates a [`Beatmap`] from the contents of a beatmap file, such as
+    /// data received over the network.
+    ///
+    /// Older formats are detected with [`BeatmapVersion::detect`] and converted
+    /// to the current format, see [`crate::v1`] and [`crate::v3`].
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(match BeatmapVersion::detect(bytes)? {
+            BeatmapVersion::V2 => serde_json::from_slice::<BeatmapV1>(bytes)?.into(),
+            BeatmapVersion::V3 => serde_json::from_slice::<BeatmapV3>(bytes)?.into(),
+            BeatmapVersion::V4 => serde_json::from_slice(bytes)?,
+        })
+    }
+
+    /// Instantiates a [`Beatmap`] from the contents of a v4 beatmap file,
+    /// failing if it has any top-level field this crate does not know of, such
+    /// as a misspelled `colorNote`.
+    ///
+    /// The error message names the offending field. Fields of the objects
+    /// within the file are not checked.
+    pub fn from_str_strict(s: &str) -> Result<Self, Error> {
+        serde_json::from_str::<StrictBeatmap>(s)?;
+
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Instantiates a [`Beatmap`] from the contents of a v4 beatmap file which
+    /// may place objects off of the standard grid, as Mapping Extensions does.
+    ///
+    /// Positions off of the standard grid are clamped to the nearest cell of
+    /// it in the returned [`Beatmap`], and their original values are kept in
+    /// the returned [`ExtendedGrid`], which can put them back with
+    /// [`ExtendedGrid::to_value`].
+    pub fn from_slice_extended(bytes: &[u8]) -> Result<(Self, ExtendedGrid), Error> {
+        let mut value: serde_json::Value = serde_json::from_slice(bytes)?;
+        let mut extended_grid = ExtendedGrid::default();
+
+        for (key, [x_key, y_key], positions) in extended_grid.collections_mut() {
+            let Some(elements) = value.get_mut(key).and_then(|v| v.as_array_mut()) else {
+                continue;
+            };
+
+            for (i, element) in elements.iter_mut().enumerate() {
+                let raw = |axis| element.get(axis).and_then(serde_json::Value::as_f64);
+                let (Some(x), Some(y)) = (raw(x_key), raw(y_key)) else {
+                    continue;
+                };
+                let position = ExtendedGridPosition {
+                    x: x as i32,
+                    y: y as i32,
+                };
+
+                if position.to_standard().is_none() {
+                    let clamped = position.clamped();
+
+                    element[x_key] = json!(clamped.line_index as u8);
+                    element[y_key] = json!(clamped.line_layer as u8);
+                    positions.insert(i, position);
+                }
+            }
+        }
+
+        Ok((serde_json::from_value(value)?, extended_grid))
+    }
+
+    /// Serializes this [`Beatmap`] to a beatmap file.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.to_writer(&mut writer)?;
+
+        Ok(writer.flush()?)
+    }
+
+    /// Serializes a [`Beatmap`] as pretty-printed JSON directly into a writer,
+    /// without building the whole JSON string in memory first.
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), Error> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// Serializes this [`Beatmap`] as a v2 beatmap file, for tools and
+    /// platforms which do not support the current format.
+    ///
+    /// Color notes and bombs are resolved into flat objects in `_notes`, sorted
+    /// by beat, with bombs having a `_type` of `3`. Obstacles starting on the
+    /// top row become crouch walls and all others become full height walls,
+    /// since v2 has no other heights. Arcs, chains, angle offsets, rotation
+    /// lanes, and custom data have no v2 equivalent and are dropped, as are
+    /// objects with out of bounds indices. `_events` is always empty, since
+    /// lighting is kept in a [`crate::Lightshow`] instead.
+    pub fn to_v2_json(&self) -> Result<String, Error> {
+        let color_notes = self.resolved_color_notes().map(|(note, data)| {
+            (
+                note.beat,
+                json!({
+                    "_time": note.beat,
+                    "_lineIndex": data.grid_position.line_index as u8,
+                    "_lineLayer": data.grid_position.line_layer as u8,
+                    "_type": data.color as u8,
+                    "_cutDirection": data.cut_direction as u8,
+                }),
+            )
+        });
+        let bomb_notes = self.bomb_notes.iter().filter_map(|bomb| {
+            let data = self.bomb_notes_data.get(bomb.metadata_index)?;
+
+            Some((
+                bomb.beat,
+                json!({
+                    "_time": bomb.beat,
+                    "_lineIndex": data.line_index as u8,
+                    "_lineLayer": data.line_layer as u8,
+                    "_type": 3,
+                    "_cutDirection": 0,
+                }),
+            ))
+        });
+        let mut notes: Vec<_> = color_notes.chain(bomb_notes).collect();
+        let obstacles: Vec<_> = self
+            .obstacles
+            .iter()
+            .filter_map(|obstacle| {
+                let data = self.obstacles_data.get(obstacle.metadata_index)?;
+
+                Some(json!({
+                    "_time": obstacle.beat,
+                    "_lineIndex": data.grid_position.line_index as u8,
+                    "_type": u8::from(data.grid_position.line_layer == LineLayer::Top),
+                    "_duration": data.duration,
+                    "_width": data.width,
+                }))
+            })
+            .collect();
+
+        notes.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        Ok(serde_json::to_string(&json!({
+            "_version": "2.2.0",
+            "_notes": notes.into_iter().map(|(_, note)| note).collect::<Vec<_>>(),
+            "_obstacles": obstacles,
+            "_events": [],
+        }))?)
+    }
+
+    /// Pairs each of [`Beatmap::color_notes`] with the [`ColorNoteData`] its
+    /// [`Object::metadata_index`] points to.
+    ///
+    /// Notes whose index is out of bounds are skipped.
+    pub fn resolved_color_notes(&self) -> impl Iterator<Item = (&Object, &ColorNoteData)> {
+        self.color_notes.iter().filter_map(|note| {
+            self.color_notes_data
+                .get(note.metadata_index)
+                .map(|data| (note, data))
+        })
+    }
+
+    /// Finds pairs of color notes which are on the same grid cell at the same
+    /// time, which makes a map broken and unrankable.
+    ///
+    /// Pairs are of indices into [`Beatmap::color_notes`], the lower index
+    /// first, sorted. Notes are at the same time when their beats are within a
+    /// thousandth of a beat of each other. Notes whose index is out of bounds
+    /// are skipped. See [`Beatmap::notes_overlapping_bombs`] for bombs.
+    pub fn overlapping_notes(&self) -> Vec<(usize, usize)> {
+        let notes = self.color_note_cells();
+        let mut pairs = Vec::new();
+
+        for (i, (beat, grid_position, index)) in notes.iter().enumerate() {
+            for (other_beat, other_grid_position, other_index) in &notes[i + 1..] {
+                if other_beat - beat > SIMULTANEOUS_EPSILON {
+                    break;
+                }
+
+                if grid_position == other_grid_position {
+                    pairs.push((*index.min(other_index), *index.max(other_index)));
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Finds color notes which are on the same grid cell as a bomb at the same
+    /// time.
+    ///
+    /// Pairs are of an index into [`Beatmap::color_notes`] and an index into
+    /// [`Beatmap::bomb_notes`], sorted. Notes and bombs are at the same time
+    /// when their beats are within a thousandth of a beat of each other.
+    /// Objects with out of bounds indices are skipped.
+    pub fn notes_overlapping_bombs(&self) -> Vec<(usize, usize)> {
+        let notes = self.color_note_cells();
+        let mut pairs = Vec::new();
+
+        for (bomb_index, bomb) in self.bomb_notes.iter().enumerate() {
+            let Some(bomb_grid_position) = self.bomb_notes_data.get(bomb.metadata_index) else {
+                continue;
+            };
+            let start =
+                notes.partition_point(|(beat, _, _)| *beat < bomb.beat - SIMULTANEOUS_EPSILON);
+
+            for (beat, grid_position, note_index) in &notes[start..] {
+                if beat - bomb.beat > SIMULTANEOUS_EPSILON {
+                    break;
+                }
+
+                if *grid_position == bomb_grid_position {
+                    pairs.push((*note_index, bomb_index));
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// The beat, grid position, and index of each resolved color note, sorted
+    /// by beat.
+    fn color_note_cells(&self) -> Vec<(Beats, &GridPosition, usize)> {
+        let mut notes: Vec<_> = self
+            .color_notes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, note)| {
+                let data = self.color_notes_data.get(note.metadata_index)?;
+
+                Some((note.beat, &data.grid_position, index))
+            })
+            .collect();
+
+        notes.sort_by(|(a, _, _), (b, _, _)| a.total_cmp(b));
+        notes
+    }
+
+    /// Counts crossovers, which are pairs of simultaneous notes where the left
+    /// saber's note is on the right half of the grid and the right saber's note
+    /// is on the left half.
+    ///
+    /// The left half of the grid consists of [`LineIndex::FarLeft`] and
+    /// [`LineIndex::Left`], and the right half consists of [`LineIndex::Right`]
+    /// and [`LineIndex::FarRight`]. Notes are simultaneous when their beats are
+    /// within a thousandth of a beat of each other.
+    pub fn crossover_count(&self) -> usize {
+        let mut notes: Vec<_> = self.resolved_color_notes().collect();
+        let mut count = 0;
+
+        notes.sort_by(|(a, _), (b, _)| a.beat.total_cmp(&b.beat));
+
+        for group in notes.chunk_by(|(a, _), (b, _)| b.beat - a.beat <= SIMULTANEOUS_EPSILON) {
+            let crossed = |color| {
+                group
+                    .iter()
+                    .filter(|(_, data)| {
+                        data.color == color
+                            && match color {
+                                Color::LeftSaber => data.grid_position.line_index.is_right_half(),
+                                Color::RightSaber => !data.grid_position.line_index.is_right_half(),
+                            }
+                    })
+                    .count()
+            };
+
+            count += crossed(Color::LeftSaber) * crossed(Color::RightSaber);
+        }
+
+        count
+    }
+
+    /// The saber with substantially more notes than the other.
+    ///
+    /// A saber is considered dominant when more than 60% of the resolved color
+    /// notes belong to it. Returns [`None`] if the notes are more balanced than
+    /// that or if there are no notes at all.
+    pub fn dominant_hand(&self) -> Option<Color> {
+        let (mut left, mut right) = (0, 0);
+
+        for (_, data) in self.resolved_color_notes() {
+            match data.color {
+                Color::LeftSaber => left += 1,
+                Color::RightSaber => right += 1,
+            }
+        }
+
+        let total = (left + right) as f64;
+
+        if left as f64 > total * 0.6 {
+            Some(Color::LeftSaber)
+        } else if right as f64 > total * 0.6 {
+            Some(Color::RightSaber)
+        } else {
+            None
+        }
+    }
+
+    /// Every distinct cut direction of the resolved color notes.
+    ///
+    /// Reveals the style of a map, such as whether it uses only cardinal
+    /// directions, includes diagonals, or relies on [`CutDirection::Any`].
+    /// See [`Beatmap::cut_directions_used_by`] for a single saber.
+    pub fn cut_directions_used(&self) -> HashSet<CutDirection> {
+        self.resolved_color_notes()
+            .map(|(_, data)| data.cut_direction)
+            .collect()
+    }
+
+    /// Every distinct cut direction of the resolved color notes of a saber.
+    pub fn cut_directions_used_by(&self, color: Color) -> HashSet<CutDirection> {
+        self.resolved_color_notes()
+            .filter(|(_, data)| data.color == color)
+            .map(|(_, data)| data.cut_direction)
+            .collect()
+    }
+
+    /// Groups the resolved color notes of a saber into the swings needed to
+    /// cut them, in chronological order.
+    ///
+    /// Notes are in the same swing as the previous note of the same color if
+    /// they are at most an eighth of a beat after it, which covers stacks,
+    /// towers, and sliders.
+    pub fn swings(&self, color: Color) -> Vec<Vec<(&Object, &ColorNoteData)>> {
+        let mut notes: Vec<_> = self
+            .resolved_color_notes()
+            .filter(|(_, data)| data.color == color)
+            .collect();
+
+        notes.sort_by(|(a, _), (b, _)| a.beat.total_cmp(&b.beat));
+
+        notes
+            .chunk_by(|(a, _), (b, _)| b.beat - a.beat <= SWING_WINDOW)
+            .map(<[_]>::to_vec)
+            .collect()
+    }
+
+    /// The number of swings per second across both sabers, which accounts for
+    /// multiple notes cut in one swing, unlike a raw note count.
+    ///
+    /// Swings are grouped per saber with [`Beatmap::swings`], which can also be
+    /// used for a per-saber breakdown. The duration is measured from the start
+    /// of the song until the last color note, using a constant `bpm`. Returns
+    /// `0.0` if there are no notes.
+    pub fn swings_per_second(&self, bpm: f64) -> f64 {
+        let swings = self.swings(Color::LeftSaber).len() + self.swings(Color::RightSaber).len();
+        let duration = self
+            .resolved_color_notes()
+            .map(|(note, _)| note.beat)
+            .fold(0.0, f64::max)
+            * 60.0
+            / bpm;
+
+        if swings == 0 || !duration.is_finite() || duration <= 0.0 {
+            return 0.0;
+        }
+
+        swings as f64 / duration
+    }
+
+    /// The time (in seconds) spent actively playing, which is the sum of the
+    /// gaps between consecutive color notes, leaving out any gap longer than
+    /// `gap_threshold` seconds, such as a break section, alongside the total
+    /// duration up to the last color note.
+    ///
+    /// Dividing a note count by the active duration gives a truer density than
+    /// dividing by the total duration, which [`Beatmap::swings_per_second`]
+    /// uses. Uses a constant `bpm`.
+    pub fn active_duration(&self, bpm: f64, gap_threshold: f64) -> ActiveDuration {
+        let mut beats: Vec<Beats> = self
+            .resolved_color_notes()
+            .map(|(note, _)| note.beat)
+            .collect();
+
+        beats.sort_by(f64::total_cmp);
+
+        ActiveDuration {
+            active: beats
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]) * 60.0 / bpm)
+                .filter(|gap| *gap <= gap_threshold)
+                .sum(),
+            total: beats.last().map_or(0.0, |beat| beat * 60.0 / bpm),
+        }
+    }
+
+    /// The indices of bombs in [`Beatmap::bomb_notes`] placed to force a
+    /// parity reset, so the player has to bring their saber back the way it
+    /// came instead of swinging the other way.
+    ///
+    /// A bomb is a candidate when it is in the same column as an upward cut
+    /// note and above it, or the same column as a downward cut note and below
+    /// it, on the note's beat or up to a beat after. Diagonal cuts count as
+    /// upward or downward. Objects with out of bounds indices are skipped.
+    pub fn bomb_reset_candidates(&self) -> Vec<usize> {
+        self.bomb_notes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, bomb)| Some((i, bomb, self.bomb_notes_data.get(bomb.metadata_index)?)))
+            .filter(|(_, bomb, bomb_position)| {
+                self.resolved_color_notes().any(|(note, data)| {
+                    let offset = bomb.beat - note.beat;
+
+                    if !(-SIMULTANEOUS_EPSILON..=RESET_WINDOW).contains(&offset)
+                        || data.grid_position.line_index != bomb_position.line_index
+                    {
+                        return false;
+                    }
+
+                    let bomb_layer = bomb_position.line_layer as u8;
+                    let note_layer = data.grid_position.line_layer as u8;
+
+                    match data.cut_direction {
+                        CutDirection::Up | CutDirection::UpLeft | CutDirection::UpRight => {
+                            bomb_layer > note_layer
+                        }
+                        CutDirection::Down | CutDirection::DownLeft | CutDirection::DownRight => {
+                            bomb_layer < note_layer
+                        }
+                        _ => false,
+                    }
+                })
+            })
+            .map(|(i, _, _)| i)
+            .collect()
+    }
+
+    /// Finds the longest run of consecutive swings of a saber sharing the same
+    /// cut direction, a pattern flagged by ranking criteria.
+    ///
+    /// Notes are grouped into swings with [`Beatmap::swings`], and each swing
+    /// takes the cut direction of its first note. If several runs are equally
+    /// long, the earliest is returned. Returns [`None`] if the saber has no
+    /// notes.
+    pub fn longest_same_direction_run(&self, color: Color) -> Option<SameDirectionRun> {
+        let swings = self.swings(color);
+
+        swings
+            .chunk_by(|a, b| a[0].1.cut_direction == b[0].1.cut_direction)
+            .map(|run| SameDirectionRun {
+                cut_direction: run[0][0].1.cut_direction,
+                length: run.len(),
+                start_beat: run[0][0].0.beat,
+                end_beat: run[run.len() - 1][0].0.beat,
+            })
+            .reduce(|longest, run| {
+                if run.length > longest.length {
+                    run
+                } else {
+                    longest
+                }
+            })
+    }
+
+    /// Finds notes which are hidden behind the previous note of the same
+    /// saber when they spawn, given the note jump speed `njs` and a constant
+    /// `bpm`.
+    ///
+    /// Notes spawn a half jump duration (see
+    /// [`crate::info::DifficultyBeatmap::half_jump_duration`], without an
+    /// offset) before their beat, and are considered visually resolved once
+    /// they reach the player. A note in the same cell as the previous note of the same color
+    /// which spawns before that note has resolved can't be seen until it has,
+    /// so the pair is reported. Notes cut in the same swing (see
+    /// [`Beatmap::swings`]) are not reported, and notes with out of bounds
+    /// indices are skipped.
+    pub fn check_visual_windows(&self, njs: f64, bpm: f64) -> Vec<WindowIssue> {
+        let half_jump_duration = half_jump_duration(njs, bpm, 0.0);
+        let mut issues = Vec::new();
+
+        for color in [Color::LeftSaber, Color::RightSaber] {
+            let mut notes: Vec<_> = self
+                .color_notes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, note)| {
+                    Some((i, note, self.color_notes_data.get(note.metadata_index)?))
+                })
+                .filter(|(_, _, data)| data.color == color)
+                .collect();
+
+            notes.sort_by(|(_, a, _), (_, b, _)| a.beat.total_cmp(&b.beat));
+
+            for pair in notes.windows(2) {
+                let (first, first_note, first_data) = pair[0];
+                let (second, second_note, second_data) = pair[1];
+                let hidden_for = first_note.beat - (second_note.beat - half_jump_duration);
+
+                if second_note.beat - first_note.beat > SWING_WINDOW
+                    && first_data.grid_position == second_data.grid_position
+                    && hidden_for > 0.0
+                {
+                    issues.push(WindowIssue {
+                        first,
+                        second,
+                        hidden_for,
+                    });
+                }
+            }
+        }
+
+        issues.sort_by_key(|issue| (issue.first, issue.second));
+
+        issues
+    }
+
+    /// Sets the custom color of every note of a saber, as used by Chroma.
+    ///
+    /// `rgba` is packed like the colors of [`crate::info::ColorScheme`]. It's
+    /// written to the `color` key of [`ColorNoteData::custom_data`] as an
+    /// array of four floats from 0.0 to 1.0, in the order red, green, blue,
+    /// alpha. Other custom data is left untouched, unless it's not an object,
+    /// in which case it's replaced.
+    pub fn set_note_color(&mut self, color: Color, rgba: u32) {
+        let channels: Vec<f64> = rgba
+            .to_be_bytes()
+            .into_iter()
+            .map(|channel| channel as f64 / 255.0)
+            .collect();
+
+        for data in &mut self.color_notes_data {
+            if data.color != color {
+                continue;
+            }
+
+            let custom_data = data
+                .custom_data
+                .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+
+            if !custom_data.is_object() {
+                *custom_data = serde_json::Value::Object(Default::default());
+            }
+
+            custom_data["color"] = channels.clone().into();
+        }
+    }
+
+    /// The indices of obstacles in [`Beatmap::obstacles`] which take up either
+    /// of the two central columns, [`LineIndex::Left`] and [`LineIndex::Right`],
+    /// and so block notes from being cut there.
+    ///
+    /// Obstacles only occupying the outer columns are considered decorative and
+    /// are not included, nor are obstacles with out of bounds indices.
+    pub fn blocking_obstacles(&self) -> impl Iterator<Item = usize> + '_ {
+        self.obstacles
+            .iter()
+            .enumerate()
+            .filter(|(_, obstacle)| {
+                self.obstacles_data
+                    .get(obstacle.metadata_index)
+                    .is_some_and(ObstacleData::is_blocking)
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// The number of obstacles of each [`ObstacleKind`], such as how many crouch
+    /// walls there are.
+    ///
+    /// Kinds without any obstacles are not included, nor are obstacles with out
+    /// of bounds indices.
+    pub fn obstacle_count_by_kind(&self) -> HashMap<ObstacleKind, usize> {
+        let mut counts = HashMap::new();
+
+        for obstacle in &self.obstacles {
+            if let Some(data) = self.obstacles_data.get(obstacle.metadata_index) {
+                *counts.entry(data.kind()).or_default() += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// Rotates every object on or after `beat` by `degrees`, replacing the
+    /// deprecated [`SpawnRotation`]s.
+    ///
+    /// Rotations are cumulative: `degrees` is added to the
+    /// [`Object::rotation_lane`] of each affected object, so calling this for
+    /// 15 degrees at beat 4 and again at beat 8 leaves objects from beat 8
+    /// onwards rotated by 30 degrees. Rotations wrap around after a full
+    /// turn, keeping their sign. Arcs and chains are rotated per endpoint,
+    /// so one may start before `beat` and end after it. A [`Beatmap`] does not
+    /// know its characteristic, so it's up to the caller to keep rotations
+    /// within what the characteristic allows, such as 90 degrees overall for
+    /// [`crate::info::Characteristic::NinetyDegree`].
+    pub fn set_rotation_at(&mut self, beat: Beats, degrees: i16) {
+        let affected = |object_beat: Beats| object_beat >= beat - SIMULTANEOUS_EPSILON;
+
+        for object in self
+            .color_notes
+            .iter_mut()
+            .chain(&mut self.bomb_notes)
+            .chain(&mut self.obstacles)
+        {
+            if affected(object.beat) {
+                rotate(&mut object.rotation_lane, degrees);
+            }
+        }
+
+        for arc in &mut self.arcs {
+            if affected(arc.head_beat) {
+                rotate(&mut arc.head_rotation_lane, degrees);
+            }
+
+            if affected(arc.tail_beat) {
+                rotate(&mut arc.tail_rotation_lane, degrees);
+            }
+        }
+
+        for chain in &mut self.chains {
+            if affected(chain.head_beat) {
+                rotate(&mut chain.head_rotation_lane, degrees);
+            }
+
+            if affected(chain.tail_beat) {
+                rotate(&mut chain.tail_rotation_lane, degrees);
+            }
+        }
+    }
+
+    /// Reverses the beatmap in time, so that it plays backwards.
+    ///
+    /// Every beat is reflected around the midpoint between the first and last
+    /// beat of the beatmap, so the beatmap still occupies the same span of
+    /// time. Obstacles start where they used to end, and the heads and tails
+    /// of arcs trade beats, rotation lanes, and notes. Chains stay anchored to
+    /// their head note, since a chain's head must be a note and its tail is
+    /// only a position, so only their head beat is reflected and they keep
+    /// their length. Collections keep chronological order, and reversing twice
+    /// restores the original beatmap.
+    pub fn reverse(&mut self) {
+        // Chain tails are left out, since chains are not reflected as a whole.
+        let Some((first, last)) = self.beat_bounds_with(false) else {
+            return;
+        };
+        let reflect = |beat: Beats| first + last - beat;
+
+        for object in self.color_notes.iter_mut().chain(&mut self.bomb_notes) {
+            object.beat = reflect(object.beat);
+        }
+
+        for obstacle in &mut self.obstacles {
+            let duration = self
+                .obstacles_data
+                .get(obstacle.metadata_index)
+                .map_or(0.0, |data| data.duration);
+
+            obstacle.beat = reflect(obstacle.beat + duration);
+        }
+
+        for arc in &mut self.arcs {
+            (arc.head_beat, arc.tail_beat) = (reflect(arc.tail_beat), reflect(arc.head_beat));
+            (arc.head_rotation_lane, arc.tail_rotation_lane) =
+                (arc.tail_rotation_lane, arc.head_rotation_lane);
+            (arc.head_metadata_index, arc.tail_metadata_index) =
+                (arc.tail_metadata_index, arc.head_metadata_index);
+        }
+
+        for data in &mut self.arcs_data {
+            (data.head_multiplier, data.tail_multiplier) =
+                (data.tail_multiplier, data.head_multiplier);
+            data.mid_anchor_mode = match data.mid_anchor_mode {
+                MidAnchorMode::Straight => MidAnchorMode::Straight,
+                MidAnchorMode::Clockwise => MidAnchorMode::CounterClockwise,
+                MidAnchorMode::CounterClockwise => MidAnchorMode::Clockwise,
+            };
+        }
+
+        for chain in &mut self.chains {
+            let length = chain.tail_beat - chain.head_beat;
+
+            chain.head_beat = reflect(chain.head_beat);
+            chain.tail_beat = chain.head_beat + length;
+        }
+
+        for spawn_rotation in &mut self.spawn_rotations {
+            spawn_rotation.beat = reflect(spawn_rotation.beat);
+        }
+
+        self.color_notes.reverse();
+        self.bomb_notes.reverse();
+        self.obstacles.reverse();
+        self.arcs.reverse();
+        self.chains.reverse();
+        self.spawn_rotations.reverse();
+    }
+
+    /// Checks that every index of every object points at an existing element
+    /// of its corresponding `*_data` collection, so that indexing into them
+    /// cannot panic.
+    ///
+    /// Loading a beatmap does not check this, so that corrupt beatmaps can
+    /// still be loaded and repaired. See [`Beatmap::index_errors`] to only
+    /// list the out of bounds indices.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let errors = self.index_errors();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { errors })
+        }
+    }
+
+    /// Finds every index of an object which does not point at an existing
+    /// element of its corresponding `*_data` collection.
+    pub fn index_errors(&self) -> Vec<IndexError> {
+        let mut errors = Vec::new();
+        let mut check = |collection, object_index, field, index, len| {
+            if index >= len {
+                errors.push(IndexError {
+                    collection,
+                    object_index,
+                    field,
+                    index,
+                });
+            }
+        };
+
+        for (i, note) in self.color_notes.iter().enumerate() {
+            let len = self.color_notes_data.len();
+
+            check("color_notes", i, "metadata_index", note.metadata_index, len);
+        }
+
+        for (i, bomb) in self.bomb_notes.iter().enumerate() {
+            let len = self.bomb_notes_data.len();
+
+            check("bomb_notes", i, "metadata_index", bomb.metadata_index, len);
+        }
+
+        for (i, obstacle) in self.obstacles.iter().enumerate() {
+            let len = self.obstacles_data.len();
+
+            check(
+                "obstacles",
+                i,
+                "metadata_index",
+                obstacle.metadata_index,
+                len,
+            );
+        }
+
+        for (i, arc) in self.arcs.iter().enumerate() {
+            let len = self.color_notes_data.len();
+
+            check(
+                "arcs",
+                i,
+                "head_metadata_index",
+                arc.head_metadata_index,
+                len,
+            );
+            check(
+                "arcs",
+                i,
+                "tail_metadata_index",
+                arc.tail_metadata_index,
+                len,
+            );
+            check(
+                "arcs",
+                i,
+                "arc_metadata_index",
+                arc.arc_metadata_index,
+                self.arcs_data.len(),
+            );
+        }
+
+        for (i, chain) in self.chains.iter().enumerate() {
+            let len = self.color_notes_data.len();
+
+            check(
+                "chains",
+                i,
+                "head_metadata_index",
+                chain.head_metadata_index,
+                len,
+            );
+            check(
+                "chains",
+                i,
+                "chain_metadata_index",
+                chain.chain_metadata_index,
+                self.chains_data.len(),
+            );
+        }
+
+        for (i, spawn_rotation) in self.spawn_rotations.iter().enumerate() {
+            let len = self.spawn_rotations_data.len();
+
+            check("spawn_rotations", i, "index", spawn_rotation.index, len);
+        }
+
+        errors
+    }
+
+    /// Finds every element of a `*_data` collection which no object points at.
+    ///
+    /// Unlike the out of bounds indices found by [`Beatmap::index_errors`],
+    /// these are valid, but usually unintended. [`Beatmap::compact`] removes
+    /// them.
+    pub fn orphaned_data(&self) -> Vec<OrphanedData> {
+        let mut orphans = Vec::new();
+        let mut check = |collection, len, indices: Vec<usize>| {
+            let mut referenced = vec![false; len];
+
+            for index in indices {
+                if let Some(referenced) = referenced.get_mut(index) {
+                    *referenced = true;
+                }
+            }
+
+            orphans.extend(
+                referenced
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, referenced)| !referenced)
+                    .map(|(index, _)| OrphanedData { collection, index }),
+            );
+        };
+
+        check(
+            "color_notes_data",
+            self.color_notes_data.len(),
+            self.color_notes
+                .iter()
+                .map(|note| note.metadata_index)
+                .chain(
+                    self.arcs
+                        .iter()
+                        .flat_map(|arc| [arc.head_metadata_index, arc.tail_metadata_index]),
+                )
+                .chain(self.chains.iter().map(|chain| chain.head_metadata_index))
+                .collect(),
+        );
+        check(
+            "bomb_notes_data",
+            self.bomb_notes_data.len(),
+            self.bomb_notes
+                .iter()
+                .map(|bomb| bomb.metadata_index)
+                .collect(),
+        );
+        check(
+            "obstacles_data",
+            self.obstacles_data.len(),
+            self.obstacles
+                .iter()
+                .map(|obstacle| obstacle.metadata_index)
+                .collect(),
+        );
+        check(
+            "arcs_data",
+            self.arcs_data.len(),
+            self.arcs.iter().map(|arc| arc.arc_metadata_index).collect(),
+        );
+        check(
+            "chains_data",
+            self.chains_data.len(),
+            self.chains
+                .iter()
+                .map(|chain| chain.chain_metadata_index)
+                .collect(),
+        );
+        check(
+            "spawn_rotations_data",
+            self.spawn_rotations_data.len(),
+            self.spawn_rotations
+                .iter()
+                .map(|spawn_rotation| spawn_rotation.index)
+                .collect(),
+        );
+
+        orphans
+    }
+
+    /// Checks that the head and tail of every arc are attached to color notes.
+    ///
+    /// An endpoint is attached when a color note exists on the same beat as the
+    /// endpoint with the same position and color as the data its metadata
+    /// index points to. Returns every endpoint which is not attached,
+    /// including those with out of bounds indices.
+    pub fn validate_arc_endpoints_are_notes(&self) -> Result<(), Vec<OrphanedArcEndpoint>> {
+        let mut orphaned = Vec::new();
+
+        for (arc_index, arc) in self.arcs.iter().enumerate() {
+            for (endpoint, beat, metadata_index) in [
+                (ArcEndpoint::Head, arc.head_beat, arc.head_metadata_index),
+                (ArcEndpoint::Tail, arc.tail_beat, arc.tail_metadata_index),
+            ] {
+                let attached = self
+                    .color_notes_data
+                    .get(metadata_index)
+                    .is_some_and(|data| {
+                        self.resolved_color_notes().any(|(note, note_data)| {
+                            (note.beat - beat).abs() <= SIMULTANEOUS_EPSILON
+                                && note_data.grid_position == data.grid_position
+                                && note_data.color == data.color
+                        })
+                    });
+
+                if !attached {
+                    orphaned.push(OrphanedArcEndpoint {
+                        arc_index,
+                        endpoint,
+                    });
+                }
+            }
+        }
+
+        if orphaned.is_empty() {
+            Ok(())
+        } else {
+            Err(orphaned)
+        }
+    }
+
+    /// Sorts every collection of objects by beat, keeping the original order of
+    /// objects on the same beat. Arcs and chains are sorted by their head beat.
+    pub fn sort(&mut self) {
+        fn sort_by_beat(objects: &mut [impl Timed]) {
+            objects.sort_by(|a, b| a.beat().total_cmp(&b.beat()));
+        }
+
+        sort_by_beat(&mut self.color_notes);
+        sort_by_beat(&mut self.bomb_notes);
+        sort_by_beat(&mut self.obstacles);
+        sort_by_beat(&mut self.arcs);
+        sort_by_beat(&mut self.chains);
+        sort_by_beat(&mut self.spawn_rotations);
+    }
+
+    /// Splits the beatmap into the objects before `beat` and the objects on or
+    /// after it. Arcs and chains go by their head beat.
+    ///
+    /// If `rebase` is true, the second beatmap is shifted so that `beat` becomes
+    /// beat 0, otherwise beats are kept as they are. The `*_data` collections
+    /// of both beatmaps are [compacted](Beatmap::compact), unless any index is
+    /// out of bounds, in which case they are left as they are.
+    pub fn split_at_beat(&self, beat: Beats, rebase: bool) -> (Beatmap, Beatmap) {
+        fn split<T: Timed + Clone>(objects: &[T], beat: Beats) -> (Vec<T>, Vec<T>) {
+            objects
+                .iter()
+                .cloned()
+                .partition(|object| object.beat() < beat)
+        }
+
+        let mut before = self.clone();
+        let mut after = self.clone();
+
+        (before.color_notes, after.color_notes) = split(&self.color_notes, beat);
+        (before.bomb_notes, after.bomb_notes) = split(&self.bomb_notes, beat);
+        (before.obstacles, after.obstacles) = split(&self.obstacles, beat);
+        (before.arcs, after.arcs) = split(&self.arcs, beat);
+        (before.chains, after.chains) = split(&self.chains, beat);
+        (before.spawn_rotations, after.spawn_rotations) = split(&self.spawn_rotations, beat);
+
+        if rebase {
+            for object in after
+                .color_notes
+                .iter_mut()
+                .chain(&mut after.bomb_notes)
+                .chain(&mut after.obstacles)
+            {
+                object.beat -= beat;
+            }
+
+            for arc in &mut after.arcs {
+                arc.head_beat -= beat;
+                arc.tail_beat -= beat;
+            }
+
+            for chain in &mut after.chains {
+                chain.head_beat -= beat;
+                chain.tail_beat -= beat;
+            }
+
+            for spawn_rotation in &mut after.spawn_rotations {
+                spawn_rotation.beat -= beat;
+            }
+        }
+
+        let _ = before.compact();
+        let _ = after.compact();
+
+        (before, after)
+    }
+
+    /// Removes elements of `*_data` collections that are not referenced by any
+    /// object, or are identical to an earlier element, and updates indices to
+    /// match.
+    ///
+    /// Nothing is changed if any index is out of bounds, instead the errors
+    /// from [`Beatmap::index_errors`] are returned.
+    pub fn compact(&mut self) -> Result<(), Vec<IndexError>> {
+        let errors = self.index_errors();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        compact_data(
+            &mut self.color_notes_data,
+            self.color_notes
+                .iter_mut()
+                .map(|note| &mut note.metadata_index)
+                .chain(
+                    self.arcs.iter_mut().flat_map(|arc| {
+                        [&mut arc.head_metadata_index, &mut arc.tail_metadata_index]
+                    }),
+                )
+                .chain(
+                    self.chains
+                        .iter_mut()
+                        .map(|chain| &mut chain.head_metadata_index),
+                )
+                .collect(),
+        );
+        compact_data(
+            &mut self.bomb_notes_data,
+            self.bomb_notes
+                .iter_mut()
+                .map(|bomb| &mut bomb.metadata_index)
+                .collect(),
+        );
+        compact_data(
+            &mut self.obstacles_data,
+            self.obstacles
+                .iter_mut()
+                .map(|obstacle| &mut obstacle.metadata_index)
+                .collect(),
+        );
+        compact_data(
+            &mut self.arcs_data,
+            self.arcs
+                .iter_mut()
+                .map(|arc| &mut arc.arc_metadata_index)
+                .collect(),
+        );
+        compact_data(
+            &mut self.chains_data,
+            self.chains
+                .iter_mut()
+                .map(|chain| &mut chain.chain_metadata_index)
+                .collect(),
+        );
+        compact_data(
+            &mut self.spawn_rotations_data,
+            self.spawn_rotations
+                .iter_mut()
+                .map(|spawn_rotation| &mut spawn_rotation.index)
+                .collect(),
+        );
+
+        Ok(())
+    }
+
+    /// Every color note, bomb, obstacle, arc, and chain, sorted by beat.
+    ///
+    /// Arcs and chains are sorted by their head beat. Objects on the same beat
+    /// are in the order above, and those of the same kind keep their order in
+    /// the beatmap.
+    pub fn timeline(&self) -> Vec<TimelineItem<'_>> {
+        let mut timeline: Vec<_> = self
+            .color_notes
+            .iter()
+            .map(TimelineItem::ColorNote)
+            .chain(self.bomb_notes.iter().map(TimelineItem::BombNote))
+            .chain(self.obstacles.iter().map(TimelineItem::Obstacle))
+            .chain(self.arcs.iter().map(TimelineItem::Arc))
+            .chain(self.chains.iter().map(TimelineItem::Chain))
+            .collect();
+
+        timeline.sort_by(|a, b| a.beat().total_cmp(&b.beat()));
+
+        timeline
+    }
+
+    /// The number of color notes, not counting bombs, arcs, or chains.
+    pub fn note_count(&self) -> usize {
+        self.color_notes.len()
+    }
+
+    /// The number of bombs.
+    pub fn bomb_count(&self) -> usize {
+        self.bomb_notes.len()
+    }
+
+    /// The number of obstacles.
+    pub fn obstacle_count(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    /// The latest beat any object ends on, or `0.0` if there are no objects.
+    ///
+    /// This is the upper bound of [`Beatmap::beat_bounds`], so it accounts for
+    /// the end of obstacles and the tails of arcs and chains.
+    pub fn last_beat(&self) -> Beats {
+        self.beat_bounds().map_or(0.0, |(_, last)| last)
+    }
+
+    /// The number of color notes per second, from the start of the song until
+    /// [`Beatmap::last_beat`], using the BPM of `audio`.
+    ///
+    /// This assumes a constant BPM, so BPM changes in
+    /// [`crate::audio::Audio::bpm_data`] are not accounted for. Returns `0.0`
+    /// if there are no notes or the duration is zero.
+    pub fn nps(&self, audio: &Audio) -> f64 {
+        let duration = self.last_beat() * 60.0 / audio.bpm;
+
+        if self.note_count() == 0 || !duration.is_finite() || duration <= 0.0 {
+            return 0.0;
+        }
+
+        self.note_count() as f64 / duration
+    }
+
+    /// The earliest beat any object starts on and the latest beat any object
+    /// ends on, or [`None`] if there are no objects.
+    ///
+    /// Obstacles end after their [`ObstacleData::duration`], and arcs and
+    /// chains end on their tail beat, so the upper bound may be later than the
+    /// beat of any object.
+    pub fn beat_bounds(&self) -> Option<(Beats, Beats)> {
+        self.beat_bounds_with(true)
+    }
+
+    /// Like [`Beatmap::beat_bounds`], but chains only span their head beat if
+    /// `chain_tails` is `false`.
+    fn beat_bounds_with(&self, chain_tails: bool) -> Option<(Beats, Beats)> {
+        let obstacles = self.obstacles.iter().map(|obstacle| {
+            let duration = self
+                .obstacles_data
+                .get(obstacle.metadata_index)
+                .map_or(0.0, |data| data.duration);
+
+            (obstacle.beat, obstacle.beat + duration)
+        });
+        let arcs = self.arcs.iter().map(|arc| (arc.head_beat, arc.tail_beat));
+        let chains = self.chains.iter().map(|chain| {
+            let end = if chain_tails {
+                chain.tail_beat
+            } else {
+                chain.head_beat
+            };
+
+            (chain.head_beat, end)
+        });
+
+        self.color_notes
+            .iter()
+            .chain(&self.bomb_notes)
+            .map(|object| (object.beat, object.beat))
+            .chain(obstacles)
+            .chain(arcs)
+            .chain(chains)
+            .chain(
+                self.spawn_rotations
+                    .iter()
+                    .map(|event| (event.beat, event.beat)),
+            )
+            .reduce(|(first, last), (start, end)| (first.min(start), last.max(end)))
+    }
+}
+
+/// An endpoint of an arc which is not attached to a color note, see
+/// [`Beatmap::validate_arc_endpoints_are_notes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedArcEndpoint {
+    /// The index of the arc in [`Beatmap::arcs`].
+    pub arc_index: usize,
+    /// Which endpoint of the arc is not attached.
+    pub endpoint: ArcEndpoint,
+}
+
+/// Either end of an [`Arc`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArcEndpoint {
+    Head,
+    Tail,
+}
+
+/// A run of consecutive swings sharing the same cut direction, see
+/// [`Beatmap::longest_same_direction_run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SameDirectionRun {
+    /// The cut direction shared by the swings.
+    pub cut_direction: CutDirection,
+    /// The number of swings in the run.
+    pub length: usize,
+    /// The beat of the first swing in the run.
+    pub start_beat: Beats,
+    /// The beat of the last swing in the run.
+    pub end_beat: Beats,
+}
+
+/// The active and total play time of a [`Beatmap`], see
+/// [`Beatmap::active_duration`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveDuration {
+    /// The time (in seconds) spent actively playing, leaving out long gaps.
+    pub active: f64,
+    /// The time (in seconds) from the start of the song to the last color
+    /// note.
+    pub total: f64,
+}
+
+/// A note hidden behind the previous note of the same saber when it spawns,
+/// see [`Beatmap::check_visual_windows`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowIssue {
+    /// The index of the earlier note in [`Beatmap::color_notes`].
+    pub first: usize,
+    /// The index of the hidden note in [`Beatmap::color_notes`].
+    pub second: usize,
+    /// How long (in beats) the hidden note can't be seen for after spawning.
+    pub hidden_for: Beats,
+}
+
+/// Adds `degrees` to `rotation_lane`, wrapping around after a full turn.
+fn rotate(rotation_lane: &mut i16, degrees: i16) {
+    *rotation_lane = ((i32::from(*rotation_lane) + i32::from(degrees)) % 360) as i16;
+}
+
+/// Removes unreferenced and duplicate elements of `data`, keeping the order of
+/// those remaining, and updates `indices` to point at the remaining elements.
+///
+/// Elements are bucketed by their serialized JSON, since most of them hold
+/// floats and can't be hashed, and only compared within a bucket.
+///
+/// Every index must be in bounds.
+fn compact_data<T: PartialEq + Serialize>(data: &mut Vec<T>, mut indices: Vec<&mut usize>) {
+    let mut referenced = vec![false; data.len()];
+
+    for index in &indices {
+        referenced[**index] = true;
+    }
+
+    let mut remap = vec![0; data.len()];
+    let mut compacted: Vec<T> = Vec::new();
+    let mut buckets: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
+
+    for (i, element) in std::mem::take(data).into_iter().enumerate() {
+        if !referenced[i] {
+            continue;
+        }
+
+        let bucket = buckets
+            .entry(serde_json::to_vec(&element).unwrap_or_default())
+            .or_default();
+
+        remap[i] = match bucket
+            .iter()
+            .copied()
+            .find(|&position| compacted[position] == element)
+        {
+            Some(position) => position,
+            None => {
+                bucket.push(compacted.len());
+                compacted.push(element);
+                compacted.len() - 1
+            }
+        };
+    }
+
+    for index in &mut indices {
+        **index = remap[**index];
+    }
+
+    *data = compacted;
+}
+
+/// An index of an object in a [`Beatmap`] which does not point at an existing
+/// element, see [`Beatmap::index_errors`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("`{field}` of `{collection}[{object_index}]` is out of bounds: {index}")]
+pub struct IndexError {
+    /// The collection containing the object, such as `"color_notes"`.
+    pub collection: &'static str,
+    /// The index of the object within its collection.
+    pub object_index: usize,
+    /// The field containing the out of bounds index, such as
+    /// `"metadata_index"`.
+    pub field: &'static str,
+    /// The out of bounds index.
+    pub index: usize,
+}
+
+/// An element of a `*_data` collection of a [`Beatmap`] which no object points
+/// at, see [`Beatmap::orphaned_data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedData {
+    /// The collection containing the element, such as `"color_notes_data"`.
+    pub collection: &'static str,
+    /// The index of the element within its collection.
+    pub index: usize,
+}
+
+/// Every out of bounds index of a [`Beatmap`], see [`Beatmap::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Beatmap has out of bounds indices: {}",
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+)]
+pub struct ValidationError {
+    /// The out of bounds indices, of which there is at least one.
+    pub errors: Vec<IndexError>,
+}
+
+/// Any placement with a position in time.
+///
+/// This allows objects of different types to be sorted and queried together.
+///
+/// # Examples
+///
+/// ```
+/// use beat_saber_map::beatmap::{Arc, Object, Timed};
+///
+/// let mut objects: Vec<Box<dyn Timed>> = vec![
+///     Box::new(Object { beat: 2.0, ..Default::default() }),
+///     Box::new(Arc { head_beat: 1.0, tail_beat: 3.0, ..Default::default() }),
+/// ];
+///
+/// objects.sort_by(|a, b| a.beat().total_cmp(&b.beat()));
+///
+/// assert_eq!(objects[0].end_beat(), 3.0);
+/// ```
+pub trait Timed {
+    /// The beat this starts on. For arcs and chains, this is the head beat.
+    fn beat(&self) -> Beats;
+
+    /// The beat this ends on. For arcs and chains, this is the tail beat,
+    /// otherwise it's the same as [`Timed::beat`].
+    fn end_beat(&self) -> Beats {
+        self.beat()
+    }
+}
+
+impl Timed for Object {
+    fn beat(&self) -> Beats {
+        self.beat
+    }
+}
+
+impl Timed for Arc {
+    fn beat(&self) -> Beats {
+        self.head_beat
+    }
+
+    fn end_beat(&self) -> Beats {
+        self.tail_beat
+    }
+}
+
+impl Timed for Chain {
+    fn beat(&self) -> Beats {
+        self.head_beat
+    }
+
+    fn end_beat(&self) -> Beats {
+        self.tail_beat
+    }
+}
+
+impl Timed for SpawnRotation {
+    fn beat(&self) -> Beats {
+        self.beat
+    }
+}
+
+/// An object of a [`Beatmap`] of any kind, see [`Beatmap::timeline`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineItem<'a> {
+    /// An element of [`Beatmap::color_notes`].
+    ColorNote(&'a Object),
+    /// An element of [`Beatmap::bomb_notes`].
+    BombNote(&'a Object),
+    /// An element of [`Beatmap::obstacles`].
+    Obstacle(&'a Object),
+    /// An element of [`Beatmap::arcs`].
+    Arc(&'a Arc),
+    /// An element of [`Beatmap::chains`].
+    Chain(&'a Chain),
+}
+
+impl Timed for TimelineItem<'_> {
+    fn beat(&self) -> Beats {
+        match self {
+            Self::ColorNote(object) | Self::BombNote(object) | Self::Obstacle(object) => {
+                object.beat()
+            }
+            Self::Arc(arc) => arc.beat(),
+            Self::Chain(chain) => chain.beat(),
+        }
+    }
+
+    /// For obstacles, this is the same as [`Timed::beat`], since their
+    /// duration is in [`Beatmap::obstacles_data`].
+    fn end_beat(&self) -> Beats {
+        match self {
+            Self::ColorNote(object) | Self::BombNote(object) | Self::Obstacle(object) => {
+                object.end_beat()
+            }
+            Self::Arc(arc) => arc.end_beat(),
+            Self::Chain(chain) => chain.end_beat(),
+        }
+    }
+}
+
+/// The major version of the format of a beatmap file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BeatmapVersion {
+    /// Notes, bombs, and obstacles in `_notes` and `_obstacles`, as written by
+    /// v2 and earlier beatmaps. Converted with [`crate::v1::BeatmapV1`], since
+    /// these are laid out the same in v1, so arcs added in v2.6 are dropped.
+    V2,
+    /// Objects with their attributes inline, see [`crate::v3::BeatmapV3`].
+    V3,
+    /// The current format, see [`Beatmap`].
+    V4,
+}
+
+impl BeatmapVersion {
+    /// Detects the version of the beatmap file with the given contents from
+    /// its `version` or `_version` field.
+    ///
+    /// Any file with a `_version` field is considered [`BeatmapVersion::V2`],
+    /// and files with neither field or an unknown `version` are considered
+    /// [`BeatmapVersion::V4`].
+    pub fn detect(bytes: &[u8]) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct Versions {
+            version: Option<String>,
+            #[serde(rename = "_version")]
+            legacy_version: Option<String>,
+        }
+
+        let versions: Versions = serde_json::from_slice(bytes)?;
+
+        Ok(match (versions.version, versions.legacy_version) {
+            (Some(version), _) if version.starts_with("3.") => Self::V3,
+            (None, Some(_)) => Self::V2,
+            _ => Self::V4,
+        })
     }
 }
 
@@ -96,6 +1666,9 @@ pub struct Object {
     /// The index of corresponding data in `*_data` of [`Beatmap`].
     #[serde(rename = "i")]
     pub metadata_index: usize,
+    /// Additional data used by mods, such as Noodle Extensions' `track`.
+    #[serde(rename = "customData", skip_serializing_if = "Option::is_none")]
+    pub custom_data: Option<serde_json::Value>,
 }
 
 /// The attributes of a color note.
@@ -117,26 +1690,186 @@ pub struct ColorNoteData {
     #[doc = bsmg_wiki!("beatmap"#"color-notes-angle-offset")]
     #[serde(rename = "a")]
     pub angle_offset: i16,
+    /// Additional data used by mods, such as Chroma's `color`.
+    #[serde(rename = "customData", skip_serializing_if = "Option::is_none")]
+    pub custom_data: Option<serde_json::Value>,
+}
+
+impl ColorNoteData {
+    /// Creates a [`ColorNoteData`] at column `x` and row `y` of the grid, with
+    /// no angle offset.
+    ///
+    /// Returns an error if `x` or `y` is outside of the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use beat_saber_map::beatmap::{Color, ColorNoteData, CutDirection, LineIndex, LineLayer};
+    ///
+    /// let data = ColorNoteData::new(1, 0, Color::LeftSaber, CutDirection::Down).unwrap();
+    ///
+    /// assert_eq!(data.grid_position.line_index, LineIndex::Left);
+    /// assert_eq!(data.grid_position.line_layer, LineLayer::Bottom);
+    /// assert!(ColorNoteData::new(4, 0, Color::LeftSaber, CutDirection::Down).is_err());
+    /// ```
+    pub fn new(x: u8, y: u8, color: Color, cut_direction: CutDirection) -> Result<Self, Error> {
+        Ok(Self {
+            grid_position: GridPosition {
+                line_index: x.try_into()?,
+                line_layer: y.try_into()?,
+            },
+            color,
+            cut_direction,
+            angle_offset: 0,
+            custom_data: None,
+        })
+    }
+}
+
+/// The grid position of an obstacle.
+#[doc = bsmg_wiki!("beatmap"#"color-notes")]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GridPosition {
+    /// See [`LineIndex`].
+    #[serde(rename = "x")]
+    pub line_index: LineIndex,
+    /// See [`LineLayer`].
+    #[serde(rename = "y")]
+    pub line_layer: LineLayer,
+}
+
+/// A position which may be off of the standard grid, as used by Mapping
+/// Extensions.
+///
+/// The standard grid is made up of `x` values from 0 to 3, which are the
+/// variants of [`LineIndex`] in order, and `y` values from 0 to 2, which are
+/// the variants of [`LineLayer`] in order. Anything else, including negative
+/// values, is off of the standard grid and can't be represented by a
+/// [`GridPosition`], see [`Beatmap::from_slice_extended`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExtendedGridPosition {
+    /// The raw horizontal position, see [`LineIndex`].
+    pub x: i32,
+    /// The raw vertical position, see [`LineLayer`].
+    pub y: i32,
+}
+
+impl ExtendedGridPosition {
+    /// Converts this into a [`GridPosition`], or [`None`] if it's off of the
+    /// standard grid.
+    pub fn to_standard(self) -> Option<GridPosition> {
+        Some(GridPosition {
+            line_index: LineIndex::from_u8(self.x.try_into().ok()?).ok()?,
+            line_layer: LineLayer::from_u8(self.y.try_into().ok()?).ok()?,
+        })
+    }
+
+    /// The cell of the standard grid nearest to this.
+    pub fn clamped(self) -> GridPosition {
+        ExtendedGridPosition {
+            x: self.x.clamp(0, 3),
+            y: self.y.clamp(0, 2),
+        }
+        .to_standard()
+        .unwrap_or_default()
+    }
+}
+
+impl From<GridPosition> for ExtendedGridPosition {
+    fn from(value: GridPosition) -> Self {
+        Self {
+            x: value.line_index.as_u8().into(),
+            y: value.line_layer.as_u8().into(),
+        }
+    }
+}
+
+/// The original positions of objects placed off of the standard grid, see
+/// [`Beatmap::from_slice_extended`].
+///
+/// # Indices
+///
+/// Positions are keyed by the index of the element they belong to, not stored
+/// on the element itself. Anything which removes or reorders elements between
+/// loading and [`ExtendedGrid::to_value`], such as [`Beatmap::compact`] or
+/// sorting, puts positions back onto the wrong elements. Only edit elements in
+/// place, or push new ones, while an [`ExtendedGrid`] is kept around.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExtendedGrid {
+    /// Positions of elements of [`Beatmap::color_notes_data`].
+    pub color_notes_data: HashMap<usize, ExtendedGridPosition>,
+    /// Positions of elements of [`Beatmap::bomb_notes_data`].
+    pub bomb_notes_data: HashMap<usize, ExtendedGridPosition>,
+    /// Positions of elements of [`Beatmap::obstacles_data`].
+    pub obstacles_data: HashMap<usize, ExtendedGridPosition>,
+    /// Tail positions of elements of [`Beatmap::chains_data`].
+    pub chains_data: HashMap<usize, ExtendedGridPosition>,
+}
+
+impl ExtendedGrid {
+    /// Whether every position is on the standard grid.
+    pub fn is_empty(&self) -> bool {
+        self.color_notes_data.is_empty()
+            && self.bomb_notes_data.is_empty()
+            && self.obstacles_data.is_empty()
+            && self.chains_data.is_empty()
+    }
+
+    /// Serializes `beatmap` into JSON with the positions of this put back, the
+    /// inverse of [`Beatmap::from_slice_extended`].
+    ///
+    /// Positions of elements which no longer exist are skipped.
+    pub fn to_value(&self, beatmap: &Beatmap) -> Result<serde_json::Value, Error> {
+        let mut value = serde_json::to_value(beatmap)?;
+
+        for (key, [x_key, y_key], positions) in self.collections() {
+            let Some(elements) = value.get_mut(key).and_then(|v| v.as_array_mut()) else {
+                continue;
+            };
+
+            for (&i, position) in positions {
+                if let Some(element) = elements.get_mut(i) {
+                    element[x_key] = json!(position.x);
+                    element[y_key] = json!(position.y);
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Each collection along with the key of its elements in JSON and the keys
+    /// of the position within each element.
+    fn collections(&self) -> [(&'static str, [&'static str; 2], &Positions); 4] {
+        [
+            ("colorNotesData", ["x", "y"], &self.color_notes_data),
+            ("bombNotesData", ["x", "y"], &self.bomb_notes_data),
+            ("obstaclesData", ["x", "y"], &self.obstacles_data),
+            ("chainsData", ["tx", "ty"], &self.chains_data),
+        ]
+    }
+
+    /// Like [`ExtendedGrid::collections`], but mutable.
+    fn collections_mut(&mut self) -> [(&'static str, [&'static str; 2], &mut Positions); 4] {
+        [
+            ("colorNotesData", ["x", "y"], &mut self.color_notes_data),
+            ("bombNotesData", ["x", "y"], &mut self.bomb_notes_data),
+            ("obstaclesData", ["x", "y"], &mut self.obstacles_data),
+            ("chainsData", ["tx", "ty"], &mut self.chains_data),
+        ]
+    }
 }
 
-/// The grid position of an obstacle.
-#[doc = bsmg_wiki!("beatmap"#"color-notes")]
-#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(default)]
-pub struct GridPosition {
-    /// See [`LineIndex`].
-    #[serde(rename = "x")]
-    pub line_index: LineIndex,
-    /// See [`LineLayer`].
-    #[serde(rename = "y")]
-    pub line_layer: LineLayer,
-}
+/// The positions of one collection of an [`ExtendedGrid`].
+type Positions = HashMap<usize, ExtendedGridPosition>;
 
 /// The horizontal row where an object should reside on the grid.
 #[doc = bsmg_wiki!("beatmap"#"color-notes-line-index")]
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(try_from = "u8", into = "u8")]
+#[serde(try_from = "LenientU8", into = "u8")]
 pub enum LineIndex {
     #[default]
     FarLeft,
@@ -159,9 +1892,16 @@ impl TryFrom<u8> for LineIndex {
     }
 }
 
-impl Into<u8> for LineIndex {
-    fn into(self) -> u8 {
-        self as u8
+impl LineIndex {
+    /// Whether this is [`LineIndex::Right`] or [`LineIndex::FarRight`].
+    pub fn is_right_half(self) -> bool {
+        matches!(self, Self::Right | Self::FarRight)
+    }
+}
+
+impl From<LineIndex> for u8 {
+    fn from(value: LineIndex) -> Self {
+        value as u8
     }
 }
 
@@ -169,7 +1909,7 @@ impl Into<u8> for LineIndex {
 #[doc = bsmg_wiki!("beatmap"#"color-notes-line-layer")]
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(try_from = "u8", into = "u8")]
+#[serde(try_from = "LenientU8", into = "u8")]
 pub enum LineLayer {
     #[default]
     Bottom,
@@ -190,9 +1930,9 @@ impl TryFrom<u8> for LineLayer {
     }
 }
 
-impl Into<u8> for LineLayer {
-    fn into(self) -> u8 {
-        self as u8
+impl From<LineLayer> for u8 {
+    fn from(value: LineLayer) -> Self {
+        value as u8
     }
 }
 
@@ -200,7 +1940,7 @@ impl Into<u8> for LineLayer {
 #[doc = bsmg_wiki!("beatmap"#"color-notes-type")]
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(try_from = "u8", into = "u8")]
+#[serde(try_from = "LenientU8", into = "u8")]
 pub enum Color {
     #[default]
     LeftSaber,
@@ -219,17 +1959,17 @@ impl TryFrom<u8> for Color {
     }
 }
 
-impl Into<u8> for Color {
-    fn into(self) -> u8 {
-        self as u8
+impl From<Color> for u8 {
+    fn from(value: Color) -> Self {
+        value as u8
     }
 }
 
 /// The direction the player should swing to successfully cut a note.
 #[doc = bsmg_wiki!("beatmap"#"color-notes-cut-direction")]
 #[allow(missing_docs)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(try_from = "u8", into = "u8")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(try_from = "LenientU8", into = "u8")]
 pub enum CutDirection {
     #[default]
     Up,
@@ -262,9 +2002,9 @@ impl TryFrom<u8> for CutDirection {
     }
 }
 
-impl Into<u8> for CutDirection {
-    fn into(self) -> u8 {
-        self as u8
+impl From<CutDirection> for u8 {
+    fn from(value: CutDirection) -> Self {
+        value as u8
     }
 }
 
@@ -290,6 +2030,61 @@ pub struct ObstacleData {
     #[doc = bsmg_wiki!("beatmap"#"obstacles-height")]
     #[serde(rename = "h")]
     pub height: i8,
+    /// Additional data used by mods, such as Chroma's `color`.
+    #[serde(rename = "customData", skip_serializing_if = "Option::is_none")]
+    pub custom_data: Option<serde_json::Value>,
+}
+
+impl ObstacleData {
+    /// The columns of the grid the obstacle takes up, where `0` is
+    /// [`LineIndex::FarLeft`] and `3` is [`LineIndex::FarRight`].
+    ///
+    /// A negative [`ObstacleData::width`] extends the obstacle to the left.
+    pub fn occupied_columns(&self) -> Range<i16> {
+        let x = self.grid_position.line_index as i16;
+        let width = self.width as i16;
+
+        if width < 0 {
+            x + width..x
+        } else {
+            x..x + width
+        }
+    }
+
+    /// Whether the obstacle takes up either of the two central columns,
+    /// [`LineIndex::Left`] and [`LineIndex::Right`], and so blocks notes from
+    /// being cut there.
+    pub fn is_blocking(&self) -> bool {
+        let columns = self.occupied_columns();
+
+        columns.start < 3 && columns.end > 1
+    }
+
+    /// Classifies the obstacle by how the player has to avoid it, see
+    /// [`ObstacleKind`].
+    pub fn kind(&self) -> ObstacleKind {
+        if !self.is_blocking() {
+            ObstacleKind::Decorative
+        } else if self.grid_position.line_layer == LineLayer::Top {
+            ObstacleKind::Crouch
+        } else {
+            ObstacleKind::Dodge
+        }
+    }
+}
+
+/// How the player has to avoid an obstacle, see [`ObstacleData::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObstacleKind {
+    /// A wall in either of the central columns which the player has to step
+    /// aside to dodge.
+    Dodge,
+    /// A wall in either of the central columns starting from the top row,
+    /// which the player has to crouch under.
+    Crouch,
+    /// A wall only in the outer columns, which the player does not have to
+    /// avoid.
+    Decorative,
 }
 
 /// The placement of an arc.
@@ -358,7 +2153,7 @@ pub struct ArcData {
 #[doc = bsmg_wiki!("beatmap"#"arcs-mid-anchor-mode")]
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(try_from = "u8", into = "u8")]
+#[serde(try_from = "LenientU8", into = "u8")]
 pub enum MidAnchorMode {
     #[default]
     Straight,
@@ -379,9 +2174,9 @@ impl TryFrom<u8> for MidAnchorMode {
     }
 }
 
-impl Into<u8> for MidAnchorMode {
-    fn into(self) -> u8 {
-        self as u8
+impl From<MidAnchorMode> for u8 {
+    fn from(value: MidAnchorMode) -> Self {
+        value as u8
     }
 }
 
@@ -492,7 +2287,7 @@ pub struct SpawnRotationData {
 #[allow(missing_docs)]
 #[deprecated = "`beatmap::SpawnRotationData` is deprecated"]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
-#[serde(try_from = "u8", into = "u8")]
+#[serde(try_from = "LenientU8", into = "u8")]
 pub enum ExecutionTime {
     #[default]
     Early,
@@ -511,9 +2306,9 @@ impl TryFrom<u8> for ExecutionTime {
     }
 }
 
-impl Into<u8> for ExecutionTime {
-    fn into(self) -> u8 {
-        self as u8
+impl From<ExecutionTime> for u8 {
+    fn from(value: ExecutionTime) -> Self {
+        value as u8
     }
 }
 
@@ -534,6 +2329,7 @@ mod tests {
                 beat: 10.0,
                 rotation_lane: 0,
                 metadata_index: 0,
+                custom_data: None,
             }],
             color_notes_data: vec![
                 ColorNoteData {
@@ -544,6 +2340,7 @@ mod tests {
                     color: Color::LeftSaber,
                     cut_direction: CutDirection::Down,
                     angle_offset: 0,
+                    custom_data: None,
                 },
                 ColorNoteData {
                     grid_position: GridPosition {
@@ -553,12 +2350,14 @@ mod tests {
                     color: Color::LeftSaber,
                     cut_direction: CutDirection::Up,
                     angle_offset: 0,
+                    custom_data: None,
                 },
             ],
             bomb_notes: vec![Object {
                 beat: 10.0,
                 rotation_lane: 0,
                 metadata_index: 0,
+                custom_data: None,
             }],
             bomb_notes_data: vec![GridPosition {
                 line_index: LineIndex::Left,
@@ -568,6 +2367,7 @@ mod tests {
                 beat: 10.0,
                 rotation_lane: 0,
                 metadata_index: 0,
+                custom_data: None,
             }],
             obstacles_data: vec![ObstacleData {
                 duration: 5.0,
@@ -577,6 +2377,7 @@ mod tests {
                 },
                 width: 1,
                 height: 5,
+                custom_data: None,
             }],
             arcs: vec![Arc {
                 head_beat: 10.0,
@@ -626,6 +2427,7 @@ mod tests {
                     magnitude: 15.0,
                 },
             ],
+            custom_data: None,
         }
     }
 
@@ -636,10 +2438,887 @@ mod tests {
         assert_eq!(serialized, sample());
     }
 
+    #[test]
+    fn writes_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Normal.dat");
+
+        manual_recreation().to_file(&path).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), sample());
+    }
+
+    #[test]
+    fn writes_correctly() {
+        let mut writer = Vec::new();
+
+        manual_recreation().to_writer(&mut writer).unwrap();
+        assert_eq!(String::from_utf8(writer).unwrap(), sample());
+    }
+
     #[test]
     fn deserializes_correctly() {
         let deserialized: Beatmap = serde_json::from_str(&sample()).unwrap();
 
         assert_eq!(deserialized, manual_recreation());
     }
+
+    #[test]
+    fn deserializes_from_slice() {
+        assert_eq!(
+            Beatmap::from_slice(sample().as_bytes()).unwrap(),
+            manual_recreation()
+        );
+        assert!(matches!(
+            Beatmap::from_slice(b"not json"),
+            Err(Error::SerdeJson(_))
+        ));
+    }
+
+    #[test]
+    fn converts_to_v2() {
+        let json: serde_json::Value = serde_json::from_str(
+            &Beatmap::from_file("sample/Normal.dat")
+                .unwrap()
+                .to_v2_json()
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(json["_version"], "2.2.0");
+        assert_eq!(
+            json["_notes"],
+            serde_json::json!([
+                { "_time": 10.0, "_lineIndex": 1, "_lineLayer": 0, "_type": 0, "_cutDirection": 1 },
+                { "_time": 10.0, "_lineIndex": 1, "_lineLayer": 0, "_type": 3, "_cutDirection": 0 },
+            ])
+        );
+        assert_eq!(
+            json["_obstacles"],
+            serde_json::json!([
+                { "_time": 10.0, "_lineIndex": 1, "_type": 0, "_duration": 5.0, "_width": 1 },
+            ])
+        );
+        assert_eq!(json["_events"], serde_json::json!([]));
+
+        let mut beatmap = manual_recreation();
+
+        beatmap.color_notes_data[0].cut_direction = CutDirection::Any;
+
+        let json: serde_json::Value = serde_json::from_str(&beatmap.to_v2_json().unwrap()).unwrap();
+
+        assert_eq!(json["_notes"][0]["_cutDirection"], 8);
+    }
+
+    #[test]
+    fn converts_older_versions() {
+        let v2 = br#"{
+            "_version": "2.2.0",
+            "_notes": [{ "_time": 4, "_lineIndex": 1, "_lineLayer": 0, "_type": 1, "_cutDirection": 1 }]
+        }"#;
+        let v3 = br#"{
+            "version": "3.3.0",
+            "colorNotes": [{ "b": 4, "x": 1, "y": 0, "c": 1, "d": 1, "a": 0 }]
+        }"#;
+
+        assert_eq!(BeatmapVersion::detect(v2).unwrap(), BeatmapVersion::V2);
+        assert_eq!(BeatmapVersion::detect(v3).unwrap(), BeatmapVersion::V3);
+        assert_eq!(
+            BeatmapVersion::detect(sample().as_bytes()).unwrap(),
+            BeatmapVersion::V4
+        );
+
+        let expected = ColorNoteData::new(1, 0, Color::RightSaber, CutDirection::Down).unwrap();
+
+        for bytes in [&v2[..], &v3[..]] {
+            let beatmap = Beatmap::from_slice(bytes).unwrap();
+
+            assert_eq!(beatmap.version, "4.0.0");
+            assert_eq!(beatmap.color_notes[0].beat, 4.0);
+            assert_eq!(beatmap.color_notes_data[0], expected);
+        }
+    }
+
+    #[test]
+    fn deserializes_whole_float_enums() {
+        let data: ColorNoteData =
+            serde_json::from_str(r#"{"x":1.0,"y":2.0,"c":1.0,"d":8.0,"a":0}"#).unwrap();
+
+        assert_eq!(data.grid_position.line_index, LineIndex::Left);
+        assert_eq!(data.grid_position.line_layer, LineLayer::Top);
+        assert_eq!(data.color, Color::RightSaber);
+        assert_eq!(data.cut_direction, CutDirection::Any);
+        assert!(serde_json::from_str::<ColorNoteData>(r#"{"c":1.5}"#).is_err());
+    }
+
+    #[test]
+    fn counts_crossovers() {
+        let note = |line_index, color| ColorNoteData {
+            grid_position: GridPosition {
+                line_index,
+                line_layer: LineLayer::Bottom,
+            },
+            color,
+            ..Default::default()
+        };
+        let object = |beat, metadata_index| Object {
+            beat,
+            metadata_index,
+            ..Default::default()
+        };
+        let beatmap = Beatmap {
+            color_notes: vec![
+                object(1.0, 0),
+                object(1.0, 1),
+                object(2.0, 2),
+                object(2.0, 1),
+            ],
+            color_notes_data: vec![
+                note(LineIndex::Right, Color::LeftSaber),
+                note(LineIndex::Left, Color::RightSaber),
+                note(LineIndex::FarLeft, Color::LeftSaber),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(beatmap.crossover_count(), 1);
+    }
+
+    #[test]
+    fn reverses() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.reverse();
+
+        assert_eq!(beatmap.color_notes[0].beat, 15.0);
+        assert_eq!(beatmap.obstacles[0].beat, 10.0);
+        assert_eq!(beatmap.arcs[0].head_metadata_index, 1);
+        assert_eq!(beatmap.chains[0].head_beat, beatmap.color_notes[0].beat);
+        assert_eq!(beatmap.chains[0].tail_beat, 20.0);
+        assert_eq!(beatmap.spawn_rotations[0].beat, 10.0);
+
+        beatmap.reverse();
+
+        assert_eq!(beatmap, manual_recreation());
+    }
+
+    #[test]
+    fn sorts_timed_objects() {
+        let mut objects: Vec<Box<dyn Timed>> = vec![
+            Box::new(Chain {
+                head_beat: 3.0,
+                tail_beat: 4.0,
+                ..Default::default()
+            }),
+            Box::new(Object {
+                beat: 2.0,
+                ..Default::default()
+            }),
+            Box::new(Arc {
+                head_beat: 1.0,
+                tail_beat: 5.0,
+                ..Default::default()
+            }),
+        ];
+
+        objects.sort_by(|a, b| a.beat().total_cmp(&b.beat()));
+
+        assert_eq!(
+            objects
+                .iter()
+                .map(|object| object.beat())
+                .collect::<Vec<_>>(),
+            [1.0, 2.0, 3.0],
+        );
+        assert_eq!(objects[0].end_beat(), 5.0);
+        assert_eq!(objects[1].end_beat(), 2.0);
+    }
+
+    #[test]
+    fn finds_index_errors() {
+        let mut beatmap = manual_recreation();
+
+        assert!(beatmap.index_errors().is_empty());
+
+        beatmap.arcs[0].tail_metadata_index = 2;
+
+        assert_eq!(
+            beatmap.index_errors(),
+            [IndexError {
+                collection: "arcs",
+                object_index: 0,
+                field: "tail_metadata_index",
+                index: 2,
+            }],
+        );
+        assert!(beatmap.compact().is_err());
+    }
+
+    #[test]
+    fn validates_arc_endpoints() {
+        let mut beatmap = manual_recreation();
+
+        assert_eq!(
+            beatmap.validate_arc_endpoints_are_notes(),
+            Err(vec![OrphanedArcEndpoint {
+                arc_index: 0,
+                endpoint: ArcEndpoint::Tail,
+            }]),
+        );
+
+        beatmap.color_notes.push(Object {
+            beat: 15.0,
+            metadata_index: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(beatmap.validate_arc_endpoints_are_notes(), Ok(()));
+    }
+
+    #[test]
+    fn sorts_and_compacts() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.color_notes.insert(
+            0,
+            Object {
+                beat: 12.0,
+                metadata_index: 3,
+                ..Default::default()
+            },
+        );
+        beatmap.color_notes_data.push(ColorNoteData::default());
+        beatmap
+            .color_notes_data
+            .push(beatmap.color_notes_data[1].clone());
+        beatmap.sort();
+        beatmap.compact().unwrap();
+
+        assert_eq!(beatmap.color_notes[0].beat, 10.0);
+        assert_eq!(beatmap.color_notes[1].metadata_index, 1);
+        assert_eq!(
+            beatmap.color_notes_data,
+            manual_recreation().color_notes_data,
+        );
+    }
+
+    #[test]
+    fn groups_swings() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.color_notes.extend([10.1, 11.0].map(|beat| Object {
+            beat,
+            metadata_index: 1,
+            ..Default::default()
+        }));
+
+        let swings = beatmap.swings(Color::LeftSaber);
+
+        assert_eq!(swings.len(), 2);
+        assert_eq!(swings[0].len(), 2);
+        assert!(beatmap.swings(Color::RightSaber).is_empty());
+        assert_eq!(beatmap.swings_per_second(60.0), 2.0 / 11.0);
+        assert_eq!(Beatmap::default().swings_per_second(60.0), 0.0);
+    }
+
+    #[test]
+    fn finds_longest_same_direction_run() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.color_notes = [(1.0, 0), (2.0, 0), (3.0, 1), (4.0, 0), (5.0, 0), (6.0, 0)]
+            .map(|(beat, metadata_index)| Object {
+                beat,
+                metadata_index,
+                ..Default::default()
+            })
+            .to_vec();
+
+        assert_eq!(
+            beatmap.longest_same_direction_run(Color::LeftSaber),
+            Some(SameDirectionRun {
+                cut_direction: CutDirection::Down,
+                length: 3,
+                start_beat: 4.0,
+                end_beat: 6.0,
+            }),
+        );
+        assert_eq!(beatmap.longest_same_direction_run(Color::RightSaber), None);
+    }
+
+    #[test]
+    fn sets_note_color() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.color_notes_data[1].custom_data = Some(serde_json::json!({ "spawnEffect": false }));
+        beatmap.set_note_color(Color::LeftSaber, 0xFF00337F);
+        beatmap.set_note_color(Color::RightSaber, 0xFFFFFFFF);
+
+        assert_eq!(
+            beatmap.color_notes_data[0].custom_data,
+            Some(serde_json::json!({ "color": [1.0, 0.0, 0.2, 127.0 / 255.0] })),
+        );
+        assert_eq!(
+            beatmap.color_notes_data[1].custom_data.as_ref().unwrap()["spawnEffect"],
+            false,
+        );
+    }
+
+    #[test]
+    fn sets_rotation_at() {
+        let mut beatmap = Beatmap::default();
+
+        for beat in [0.0, 4.0, 8.0] {
+            beatmap.color_notes.push(Object {
+                beat,
+                ..Default::default()
+            });
+        }
+
+        beatmap.arcs.push(Arc {
+            head_beat: 2.0,
+            tail_beat: 6.0,
+            ..Default::default()
+        });
+        beatmap.set_rotation_at(4.0, 15);
+        beatmap.set_rotation_at(8.0, 15);
+
+        assert_eq!(
+            beatmap
+                .color_notes
+                .iter()
+                .map(|note| note.rotation_lane)
+                .collect::<Vec<_>>(),
+            [0, 15, 30],
+        );
+        assert_eq!(beatmap.arcs[0].head_rotation_lane, 0);
+        assert_eq!(beatmap.arcs[0].tail_rotation_lane, 15);
+
+        beatmap.set_rotation_at(0.0, i16::MAX);
+        beatmap.set_rotation_at(8.0, -45);
+
+        assert_eq!(
+            beatmap
+                .color_notes
+                .iter()
+                .map(|note| note.rotation_lane)
+                .collect::<Vec<_>>(),
+            [7, 22, -8],
+        );
+    }
+
+    #[test]
+    fn computes_beat_bounds() {
+        let mut beatmap = Beatmap::default();
+
+        assert_eq!(beatmap.beat_bounds(), None);
+        assert_eq!(beatmap.last_beat(), 0.0);
+        assert_eq!(beatmap.nps(&Audio::default()), 0.0);
+
+        beatmap.color_notes_data.push(ColorNoteData::default());
+        beatmap.color_notes.push(Object {
+            beat: 2.0,
+            ..Default::default()
+        });
+        beatmap.color_notes.push(Object {
+            beat: 8.0,
+            ..Default::default()
+        });
+
+        assert_eq!(beatmap.beat_bounds(), Some((2.0, 8.0)));
+
+        beatmap.obstacles_data.push(ObstacleData {
+            duration: 4.0,
+            ..Default::default()
+        });
+        beatmap.obstacles.push(Object {
+            beat: 6.0,
+            ..Default::default()
+        });
+
+        assert_eq!(beatmap.beat_bounds(), Some((2.0, 10.0)));
+
+        beatmap.arcs.push(Arc {
+            head_beat: 1.0,
+            tail_beat: 12.0,
+            ..Default::default()
+        });
+
+        assert_eq!(beatmap.beat_bounds(), Some((1.0, 12.0)));
+        assert_eq!(beatmap.last_beat(), 12.0);
+        assert_eq!(
+            beatmap.nps(&Audio {
+                bpm: 120.0,
+                ..Default::default()
+            }),
+            1.0 / 3.0
+        );
+        assert_eq!(
+            (
+                beatmap.note_count(),
+                beatmap.bomb_count(),
+                beatmap.obstacle_count()
+            ),
+            (2, 0, 1)
+        );
+    }
+
+    #[test]
+    fn finds_bomb_reset_candidates() {
+        let position = |line_layer| GridPosition {
+            line_index: LineIndex::Left,
+            line_layer,
+        };
+        let mut beatmap = Beatmap {
+            color_notes: vec![Object::default()],
+            color_notes_data: vec![ColorNoteData {
+                grid_position: position(LineLayer::Middle),
+                cut_direction: CutDirection::Down,
+                ..Default::default()
+            }],
+            bomb_notes_data: vec![
+                position(LineLayer::Bottom),
+                position(LineLayer::Top),
+                GridPosition {
+                    line_index: LineIndex::Right,
+                    line_layer: LineLayer::Bottom,
+                },
+            ],
+            ..Default::default()
+        };
+
+        for (beat, metadata_index) in [(0.5, 0), (0.5, 1), (0.5, 2), (4.0, 0)] {
+            beatmap.bomb_notes.push(Object {
+                beat,
+                metadata_index,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(beatmap.bomb_reset_candidates(), [0]);
+
+        beatmap.color_notes_data[0].cut_direction = CutDirection::UpLeft;
+
+        assert_eq!(beatmap.bomb_reset_candidates(), [1]);
+    }
+
+    #[test]
+    fn computes_active_duration() {
+        let mut beatmap = Beatmap {
+            color_notes_data: vec![ColorNoteData::default()],
+            ..Default::default()
+        };
+
+        for beat in [21.0, 0.0, 1.0, 2.0, 20.0] {
+            beatmap.color_notes.push(Object {
+                beat,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(
+            beatmap.active_duration(120.0, 2.0),
+            ActiveDuration {
+                active: 1.5,
+                total: 10.5,
+            },
+        );
+        assert_eq!(beatmap.active_duration(120.0, 10.0).active, 10.5);
+        assert_eq!(
+            Beatmap::default().active_duration(120.0, 2.0),
+            ActiveDuration {
+                active: 0.0,
+                total: 0.0,
+            },
+        );
+    }
+
+    #[test]
+    fn reserves_capacities() {
+        let beatmap = Beatmap::with_capacities(100, 10, 5, 2, 1);
+
+        assert!(beatmap.color_notes.capacity() >= 100);
+        assert!(beatmap.color_notes_data.capacity() >= 100);
+        assert!(beatmap.chains_data.capacity() >= 1);
+        assert_eq!(beatmap, Beatmap::default());
+    }
+
+    #[test]
+    fn splits_at_beat() {
+        let beatmap = Beatmap::from_file("sample/ExpertPlus.dat").unwrap();
+        let split_beat = beatmap.color_notes[beatmap.color_notes.len() / 2].beat;
+        let (before, after) = beatmap.split_at_beat(split_beat, false);
+        let notes = |beatmap: &Beatmap| -> Vec<(Object, ColorNoteData)> {
+            beatmap
+                .resolved_color_notes()
+                .map(|(note, data)| {
+                    (
+                        Object {
+                            metadata_index: 0,
+                            ..note.clone()
+                        },
+                        data.clone(),
+                    )
+                })
+                .collect()
+        };
+
+        assert!(before.color_notes.iter().all(|note| note.beat < split_beat));
+        assert!(after.color_notes.iter().all(|note| note.beat >= split_beat));
+        assert!(before.index_errors().is_empty());
+        assert!(after.index_errors().is_empty());
+        assert_eq!([notes(&before), notes(&after)].concat(), notes(&beatmap));
+        assert_eq!(
+            before.obstacles.len() + after.obstacles.len(),
+            beatmap.obstacles.len()
+        );
+        assert_eq!(before.arcs.len() + after.arcs.len(), beatmap.arcs.len());
+        assert_eq!(
+            before.chains.len() + after.chains.len(),
+            beatmap.chains.len()
+        );
+
+        let (_, rebased) = beatmap.split_at_beat(split_beat, true);
+
+        assert_eq!(rebased.color_notes[0].beat, 0.0);
+        assert_eq!(rebased.color_notes.len(), after.color_notes.len());
+    }
+
+    #[test]
+    fn finds_blocking_obstacles() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.obstacles_data.push(ObstacleData {
+            grid_position: GridPosition {
+                line_index: LineIndex::FarRight,
+                line_layer: LineLayer::Bottom,
+            },
+            width: 1,
+            ..beatmap.obstacles_data[0].clone()
+        });
+        beatmap.obstacles.push(Object {
+            metadata_index: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(beatmap.blocking_obstacles().collect::<Vec<_>>(), [0]);
+
+        beatmap.obstacles_data[1].width = -2;
+
+        assert_eq!(beatmap.blocking_obstacles().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(beatmap.obstacles_data[1].occupied_columns(), 1..3);
+    }
+
+    #[test]
+    fn finds_dominant_hand() {
+        let mut beatmap = manual_recreation();
+
+        assert_eq!(Beatmap::default().dominant_hand(), None);
+        assert_eq!(beatmap.dominant_hand(), Some(Color::LeftSaber));
+
+        beatmap.color_notes_data[1].color = Color::RightSaber;
+        beatmap.color_notes.push(Object {
+            beat: 11.0,
+            metadata_index: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(beatmap.dominant_hand(), None);
+    }
+
+    #[test]
+    fn lists_cut_directions_used() {
+        let mut beatmap = manual_recreation();
+
+        assert_eq!(
+            beatmap.cut_directions_used(),
+            HashSet::from([CutDirection::Down])
+        );
+
+        beatmap.color_notes_data[1].color = Color::RightSaber;
+        beatmap.color_notes.push(Object {
+            beat: 11.0,
+            metadata_index: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            beatmap.cut_directions_used(),
+            HashSet::from([CutDirection::Down, CutDirection::Up])
+        );
+        assert_eq!(
+            beatmap.cut_directions_used_by(Color::RightSaber),
+            HashSet::from([CutDirection::Up])
+        );
+    }
+
+    #[test]
+    fn counts_obstacles_by_kind() {
+        let mut beatmap = manual_recreation();
+        let crouch = ObstacleData {
+            grid_position: GridPosition {
+                line_index: LineIndex::FarLeft,
+                line_layer: LineLayer::Top,
+            },
+            width: 4,
+            ..beatmap.obstacles_data[0].clone()
+        };
+        let decorative = ObstacleData {
+            grid_position: GridPosition {
+                line_index: LineIndex::FarRight,
+                line_layer: LineLayer::Bottom,
+            },
+            width: 1,
+            ..beatmap.obstacles_data[0].clone()
+        };
+
+        beatmap.obstacles_data.extend([crouch, decorative]);
+
+        for metadata_index in [1, 1, 2, 3] {
+            beatmap.obstacles.push(Object {
+                metadata_index,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(
+            beatmap.obstacle_count_by_kind(),
+            HashMap::from([
+                (ObstacleKind::Dodge, 1),
+                (ObstacleKind::Crouch, 2),
+                (ObstacleKind::Decorative, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn validates_indices() {
+        let mut beatmap = manual_recreation();
+
+        assert_eq!(beatmap.validate(), Ok(()));
+
+        beatmap.chains[0].chain_metadata_index = 4;
+
+        let error = beatmap.validate().unwrap_err();
+
+        assert_eq!(
+            error.errors,
+            [IndexError {
+                collection: "chains",
+                object_index: 0,
+                field: "chain_metadata_index",
+                index: 4,
+            }]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Beatmap has out of bounds indices: `chain_metadata_index` of `chains[0]` is out of bounds: 4"
+        );
+    }
+
+    #[test]
+    fn finds_overlapping_notes() {
+        let mut beatmap = manual_recreation();
+
+        assert!(beatmap.overlapping_notes().is_empty());
+        // The sample's only note and bomb share a cell.
+        assert_eq!(beatmap.notes_overlapping_bombs(), [(0, 0)]);
+
+        beatmap.color_notes.extend([
+            Object {
+                beat: 12.0,
+                metadata_index: 1,
+                ..Default::default()
+            },
+            Object {
+                beat: 10.0005,
+                metadata_index: 0,
+                ..Default::default()
+            },
+            Object {
+                beat: 12.1,
+                metadata_index: 1,
+                ..Default::default()
+            },
+            Object {
+                beat: 9.9999,
+                metadata_index: 0,
+                ..Default::default()
+            },
+        ]);
+
+        assert_eq!(beatmap.overlapping_notes(), [(0, 2), (0, 4), (2, 4)]);
+        assert_eq!(beatmap.notes_overlapping_bombs(), [(0, 0), (2, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn checks_visual_windows() {
+        let mut beatmap = manual_recreation();
+
+        beatmap.color_notes.extend([
+            Object {
+                beat: 11.0,
+                metadata_index: 0,
+                ..Default::default()
+            },
+            Object {
+                beat: 14.0,
+                metadata_index: 0,
+                ..Default::default()
+            },
+        ]);
+
+        // A half jump duration of 2 beats.
+        assert_eq!(
+            beatmap.check_visual_windows(10.0, 120.0),
+            [WindowIssue {
+                first: 0,
+                second: 1,
+                hidden_for: 1.0,
+            }]
+        );
+        // A half jump duration of 1 beat.
+        assert!(beatmap.check_visual_windows(20.0, 120.0).is_empty());
+    }
+
+    #[test]
+    fn converts_with_inherent_u8_methods() {
+        assert_eq!(CutDirection::from_u8(8).unwrap(), CutDirection::Any);
+        assert_eq!(CutDirection::Any.as_u8(), 8);
+        assert_eq!(LineIndex::FarRight.as_u8(), 3);
+        assert!(matches!(
+            MidAnchorMode::from_u8(3),
+            Err(Error::MidAnchorModeTryFromU8(3))
+        ));
+    }
+
+    #[test]
+    fn builds_timeline() {
+        let mut beatmap = Beatmap::default();
+        let object = |beat| Object {
+            beat,
+            ..Default::default()
+        };
+
+        beatmap.color_notes = vec![object(2.0), object(1.0)];
+        beatmap.bomb_notes = vec![object(1.0)];
+        beatmap.obstacles = vec![object(0.5)];
+        beatmap.arcs = vec![Arc {
+            head_beat: 1.5,
+            tail_beat: 4.0,
+            ..Default::default()
+        }];
+
+        assert_eq!(
+            beatmap.timeline(),
+            [
+                TimelineItem::Obstacle(&beatmap.obstacles[0]),
+                TimelineItem::ColorNote(&beatmap.color_notes[1]),
+                TimelineItem::BombNote(&beatmap.bomb_notes[0]),
+                TimelineItem::Arc(&beatmap.arcs[0]),
+                TimelineItem::ColorNote(&beatmap.color_notes[0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn loads_extended_grid() {
+        let mut json: serde_json::Value = serde_json::from_str(&sample()).unwrap();
+
+        json["colorNotesData"][0]["x"] = json!(-1);
+        json["colorNotesData"][0]["y"] = json!(1000);
+        json["chainsData"][0]["tx"] = json!(5);
+
+        let bytes = serde_json::to_vec(&json).unwrap();
+
+        assert!(Beatmap::from_slice(&bytes).is_err());
+
+        let (beatmap, extended_grid) = Beatmap::from_slice_extended(&bytes).unwrap();
+
+        assert_eq!(
+            beatmap.color_notes_data[0].grid_position,
+            GridPosition {
+                line_index: LineIndex::FarLeft,
+                line_layer: LineLayer::Top,
+            }
+        );
+        assert_eq!(
+            extended_grid.color_notes_data[&0],
+            ExtendedGridPosition { x: -1, y: 1000 }
+        );
+        assert_eq!(beatmap.chains_data[0].tail_line_index, LineIndex::FarRight);
+        assert_eq!(
+            extended_grid.chains_data[&0],
+            ExtendedGridPosition { x: 5, y: 2 }
+        );
+        assert!(extended_grid.bomb_notes_data.is_empty());
+        assert_eq!(extended_grid.to_value(&beatmap).unwrap(), json);
+    }
+
+    #[test]
+    fn preserves_custom_data() {
+        let mut json: serde_json::Value = serde_json::from_str(&sample()).unwrap();
+
+        json["customData"] = json!({ "customEvents": [] });
+        json["colorNotes"][0]["customData"] = json!({ "track": "notes" });
+        json["obstaclesData"][0]["customData"] = json!({ "color": [1, 0, 0, 1] });
+
+        let beatmap: Beatmap = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(
+            beatmap.color_notes[0].custom_data,
+            Some(json!({ "track": "notes" }))
+        );
+        assert_eq!(serde_json::to_value(&beatmap).unwrap(), json);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_when_strict() {
+        assert_eq!(
+            Beatmap::from_str_strict(&sample()).unwrap(),
+            manual_recreation()
+        );
+
+        let mut json: serde_json::Value = serde_json::from_str(&sample()).unwrap();
+
+        json["colorNote"] = json!([]);
+
+        let error = Beatmap::from_str_strict(&json.to_string()).unwrap_err();
+
+        assert!(error.to_string().contains("`colorNote`"), "{error}");
+    }
+
+    #[test]
+    fn strictly_accepts_every_field() {
+        let beatmap = Beatmap {
+            custom_data: Some(json!({ "time": 1.0 })),
+            ..manual_recreation()
+        };
+        let serialized = serde_json::to_string(&beatmap).unwrap();
+
+        assert_eq!(Beatmap::from_str_strict(&serialized).unwrap(), beatmap);
+    }
+
+    #[test]
+    fn finds_orphaned_data() {
+        let mut beatmap = manual_recreation();
+
+        assert_eq!(beatmap.orphaned_data(), []);
+
+        beatmap.obstacles.clear();
+        beatmap.bomb_notes_data.push(GridPosition::default());
+
+        assert_eq!(
+            beatmap.orphaned_data(),
+            [
+                OrphanedData {
+                    collection: "bomb_notes_data",
+                    index: 1,
+                },
+                OrphanedData {
+                    collection: "obstacles_data",
+                    index: 0,
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,170 @@
+//! Contains [`ChartEvent`], a simplified form of a difficulty for importing
+//! Beat Saber maps into other rhythm games.
+//!
+//! See [`crate::BeatSaberMap::to_chart_events`] to get started.
+//!
+//! # Lanes
+//!
+//! Each cell of the 4x3 grid is its own lane, numbered row by row from the
+//! bottom left, so that the lane is `line_index + 4 * line_layer`:
+//!
+//! | Row                   | Lanes        |
+//! |-----------------------|--------------|
+//! | [`LineLayer::Top`]    | 8, 9, 10, 11 |
+//! | [`LineLayer::Middle`] | 4, 5, 6, 7   |
+//! | [`LineLayer::Bottom`] | 0, 1, 2, 3   |
+//!
+//! [`LineLayer::Top`]: crate::beatmap::LineLayer::Top
+//! [`LineLayer::Middle`]: crate::beatmap::LineLayer::Middle
+//! [`LineLayer::Bottom`]: crate::beatmap::LineLayer::Bottom
+//!
+//! # Conversion
+//!
+//! Color notes become [`ChartEventKind::Hit`]s, regardless of their color and
+//! cut direction. Arcs and chains are flattened to hits: an arc to a hit at its
+//! head and at its tail, and a chain to a hit at each of its links. Obstacles
+//! become a [`ChartEventKind::AvoidStart`] and [`ChartEventKind::AvoidEnd`] in
+//! the lane of their bottom left cell, since they must be dodged rather than
+//! held. Bombs are not converted. Events of the same kind in the same lane at
+//! the same time, such as a note and the head of an arc attached to it, are
+//! merged.
+
+use crate::{beatmap::GridPosition, Beatmap, Beats};
+
+/// A single event of a simplified chart, see [the module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartEvent {
+    /// The time (in seconds) of the audio file the event happens at.
+    pub seconds: f64,
+    /// The lane the event happens in, see [the module documentation](self).
+    pub lane: u8,
+    /// See [`ChartEventKind`].
+    pub kind: ChartEventKind,
+}
+
+/// What happens in a [`ChartEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartEventKind {
+    /// A single hit, from a color note, arc, or chain.
+    Hit,
+    /// The start of a region the player must avoid, from an obstacle.
+    AvoidStart,
+    /// The end of a region the player must avoid, from an obstacle.
+    AvoidEnd,
+}
+
+/// How far apart two events may be (in seconds) while still being merged.
+const MERGE_EPSILON: f64 = 0.001;
+
+/// The lane of a cell of the grid, see [the module documentation](self).
+pub fn lane(grid_position: &GridPosition) -> u8 {
+    grid_position.line_index.as_u8() + 4 * grid_position.line_layer.as_u8()
+}
+
+/// Converts the objects of `beatmap` into events sorted by time, then by lane,
+/// using `seconds` to convert beats into seconds.
+pub(crate) fn events(beatmap: &Beatmap, seconds: impl Fn(Beats) -> f64) -> Vec<ChartEvent> {
+    let mut events = Vec::new();
+    let mut push = |beat, lane, kind| {
+        events.push(ChartEvent {
+            seconds: seconds(beat),
+            lane,
+            kind,
+        })
+    };
+
+    for (note, data) in beatmap.resolved_color_notes() {
+        push(note.beat, lane(&data.grid_position), ChartEventKind::Hit);
+    }
+
+    for arc in &beatmap.arcs {
+        let endpoints = [
+            (arc.head_beat, arc.head_metadata_index),
+            (arc.tail_beat, arc.tail_metadata_index),
+        ];
+
+        for (beat, metadata_index) in endpoints {
+            if let Some(data) = beatmap.color_notes_data.get(metadata_index) {
+                push(beat, lane(&data.grid_position), ChartEventKind::Hit);
+            }
+        }
+    }
+
+    for chain in &beatmap.chains {
+        let (Some(head), Some(data)) = (
+            beatmap.color_notes_data.get(chain.head_metadata_index),
+            beatmap.chains_data.get(chain.chain_metadata_index),
+        ) else {
+            continue;
+        };
+        let head_x = head.grid_position.line_index.as_u8() as f64;
+        let head_y = head.grid_position.line_layer.as_u8() as f64;
+        let tail_x = data.tail_line_index.as_u8() as f64;
+        let tail_y = data.tail_line_layer.as_u8() as f64;
+        let links = data.slice_count.max(2) - 1;
+
+        for link in 0..=links {
+            let progress = link as f64 / links as f64;
+            let x = (head_x + (tail_x - head_x) * progress)
+                .round()
+                .clamp(0.0, 3.0) as u8;
+            let y = (head_y + (tail_y - head_y) * progress)
+                .round()
+                .clamp(0.0, 2.0) as u8;
+            let beat = chain.head_beat + (chain.tail_beat - chain.head_beat) * progress;
+
+            push(beat, x + 4 * y, ChartEventKind::Hit);
+        }
+    }
+
+    for obstacle in &beatmap.obstacles {
+        let Some(data) = beatmap.obstacles_data.get(obstacle.metadata_index) else {
+            continue;
+        };
+        let lane = lane(&data.grid_position);
+
+        push(obstacle.beat, lane, ChartEventKind::AvoidStart);
+        push(
+            obstacle.beat + data.duration,
+            lane,
+            ChartEventKind::AvoidEnd,
+        );
+    }
+
+    events.sort_by(|a, b| {
+        a.seconds
+            .total_cmp(&b.seconds)
+            .then_with(|| a.lane.cmp(&b.lane))
+    });
+    events.dedup_by(|b, a| {
+        a.lane == b.lane && a.kind == b.kind && (b.seconds - a.seconds).abs() <= MERGE_EPSILON
+    });
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_beatmap() {
+        let beatmap = Beatmap::from_file("sample/Normal.dat").unwrap();
+        let events = events(&beatmap, |beat| beat / 2.0);
+        let summary: Vec<_> = events
+            .iter()
+            .map(|event| (event.seconds, event.lane, event.kind))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                (5.0, 1, ChartEventKind::Hit),
+                (5.0, 1, ChartEventKind::AvoidStart),
+                (6.25, 6, ChartEventKind::Hit),
+                (7.5, 1, ChartEventKind::AvoidEnd),
+                (7.5, 10, ChartEventKind::Hit),
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Contains functions for working with the RGBA colors of a map, such as
+//! [`crate::info::ColorScheme::saber_a_color`], which are stored as [`u32`]s
+//! in the order red, green, blue, alpha.
+//!
+//! # Examples
+//!
+//! ```
+//! use beat_saber_map::color;
+//!
+//! let rgba = color::from_channels(0xC8, 0x14, 0x14, 0xFF);
+//!
+//! assert_eq!(rgba, 0xC81414FF);
+//! assert_eq!(color::red(rgba), 0xC8);
+//! assert_eq!(color::alpha(rgba), 0xFF);
+//! ```
+//!
+//! See [`Rgba`] to work with the channels of a color as fields.
+
+/// The red channel of an RGBA color.
+pub const fn red(rgba: u32) -> u8 {
+    rgba.to_be_bytes()[0]
+}
+
+/// The green channel of an RGBA color.
+pub const fn green(rgba: u32) -> u8 {
+    rgba.to_be_bytes()[1]
+}
+
+/// The blue channel of an RGBA color.
+pub const fn blue(rgba: u32) -> u8 {
+    rgba.to_be_bytes()[2]
+}
+
+/// The alpha channel of an RGBA color.
+pub const fn alpha(rgba: u32) -> u8 {
+    rgba.to_be_bytes()[3]
+}
+
+/// Combines channels into an RGBA color.
+pub const fn from_channels(red: u8, green: u8, blue: u8, alpha: u8) -> u32 {
+    u32::from_be_bytes([red, green, blue, alpha])
+}
+
+/// An RGBA color split into its channels, see [`Rgba::from_u32`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rgba {
+    /// The red channel.
+    pub r: u8,
+    /// The green channel.
+    pub g: u8,
+    /// The blue channel.
+    pub b: u8,
+    /// The alpha channel.
+    pub a: u8,
+}
+
+impl Rgba {
+    /// Splits an RGBA color into its channels.
+    pub const fn from_u32(rgba: u32) -> Self {
+        Self {
+            r: red(rgba),
+            g: green(rgba),
+            b: blue(rgba),
+            a: alpha(rgba),
+        }
+    }
+
+    /// Combines the channels into an RGBA color, the inverse of
+    /// [`Rgba::from_u32`].
+    pub const fn to_u32(self) -> u32 {
+        from_channels(self.r, self.g, self.b, self.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_channels() {
+        for (r, g, b, a) in [(0, 0, 0, 0), (0xD9, 0x16, 0x16, 0xFF), (1, 2, 3, 4)] {
+            let rgba = from_channels(r, g, b, a);
+
+            assert_eq!(
+                (red(rgba), green(rgba), blue(rgba), alpha(rgba)),
+                (r, g, b, a)
+            );
+        }
+
+        assert_eq!(from_channels(0x28, 0x8E, 0xD2, 0xFF), 0x288ED2FF);
+    }
+
+    #[test]
+    fn splits_into_rgba() {
+        let rgba = Rgba::from_u32(0x288ED2FF);
+
+        assert_eq!(
+            rgba,
+            Rgba {
+                r: 0x28,
+                g: 0x8E,
+                b: 0xD2,
+                a: 0xFF,
+            }
+        );
+        assert_eq!(rgba.to_u32(), 0x288ED2FF);
+    }
+}
@@ -5,7 +5,7 @@ use serde::{
     Deserializer, Serializer,
 };
 
-const EXPECTING: &str = "an RGBA hex code string";
+const EXPECTING: &str = "an RGBA or RGB hex code string";
 
 struct HexVisitor;
 
@@ -20,11 +20,16 @@ impl<'de> Visitor<'de> for HexVisitor {
     where
         E: serde::de::Error,
     {
+        let invalid = || E::invalid_value(Unexpected::Str(v), &EXPECTING);
         let without_hash = v.trim_start_matches('#');
-        let parsed = u32::from_str_radix(without_hash, 16)
-            .map_err(|_| E::invalid_value(Unexpected::Str(&v), &EXPECTING))?;
+        let parsed = u32::from_str_radix(without_hash, 16).map_err(|_| invalid())?;
 
-        Ok(parsed)
+        // RGB hex codes are assumed to be fully opaque.
+        match without_hash.len() {
+            6 => Ok(parsed << 8 | 0xFF),
+            8 => Ok(parsed),
+            _ => Err(invalid()),
+        }
     }
 }
 
@@ -58,4 +63,18 @@ mod tests {
 
         assert_eq!(deserialize(&mut deserializer).unwrap(), 0x288ED2FF);
     }
+
+    #[test]
+    fn deserializes_rgb() {
+        let mut deserializer = serde_json::Deserializer::from_str("\"#288ED2\"");
+
+        assert_eq!(deserialize(&mut deserializer).unwrap(), 0x288ED2FF);
+    }
+
+    #[test]
+    fn rejects_other_lengths() {
+        let mut deserializer = serde_json::Deserializer::from_str("\"#288ED\"");
+
+        assert!(deserialize(&mut deserializer).is_err());
+    }
 }
@@ -3,13 +3,23 @@
 //! See [`Info`] to get started.
 
 use std::{
-    fs,
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::{color::Rgba, parse_file, Beatmap, Beats, Error};
 
-use crate::{Beats, Error};
+/// The duration (in seconds) of the in-game preview when
+/// [`Audio::preview_duration`] is `0.0`.
+pub const DEFAULT_PREVIEW_DURATION: f64 = 10.0;
 
 /// Describes basic metadata about the song and points to a map's other files.
 #[doc = bsmg_wiki!("info")]
@@ -37,7 +47,65 @@ pub struct Info {
     #[doc = bsmg_wiki!("info"#"color-schemes")]
     pub color_schemes: Vec<ColorScheme>,
     /// See [`DifficultyBeatmap`].
+    ///
+    /// Some tools write this as an object rather than an array, in which case
+    /// its values are read in order and its keys are discarded.
+    #[serde(deserialize_with = "deserialize_difficulty_beatmaps")]
     pub difficulty_beatmaps: Vec<DifficultyBeatmap>,
+    /// See [`InfoCustomData`].
+    #[serde(skip_serializing_if = "InfoCustomData::is_empty")]
+    pub custom_data: InfoCustomData,
+}
+
+/// The number of beats it takes for an object moving at `njs` to travel from
+/// where it spawns to the player, see [`DifficultyBeatmap::half_jump_duration`].
+pub(crate) fn half_jump_duration(njs: f64, bpm: Beats, offset: Beats) -> Beats {
+    let seconds_per_beat = 60.0 / bpm;
+    let mut half_jump_duration = 4.0;
+
+    while njs * seconds_per_beat * half_jump_duration > 17.999 {
+        half_jump_duration /= 2.0;
+    }
+
+    (half_jump_duration + offset).max(0.25)
+}
+
+fn deserialize_difficulty_beatmaps<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<DifficultyBeatmap>, D::Error> {
+    struct DifficultyBeatmapsVisitor;
+
+    impl<'de> Visitor<'de> for DifficultyBeatmapsVisitor {
+        type Value = Vec<DifficultyBeatmap>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an array or object of difficulty beatmaps")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut difficulty_beatmaps = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some(difficulty_beatmap) = seq.next_element()? {
+                difficulty_beatmaps.push(difficulty_beatmap);
+            }
+
+            Ok(difficulty_beatmaps)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut difficulty_beatmaps = Vec::with_capacity(map.size_hint().unwrap_or_default());
+
+            while let Some((_, difficulty_beatmap)) =
+                map.next_entry::<IgnoredAny, DifficultyBeatmap>()?
+            {
+                difficulty_beatmaps.push(difficulty_beatmap);
+            }
+
+            Ok(difficulty_beatmaps)
+        }
+    }
+
+    deserializer.deserialize_any(DifficultyBeatmapsVisitor)
 }
 
 impl Default for Info {
@@ -51,17 +119,375 @@ impl Default for Info {
             environment_names: Default::default(),
             color_schemes: Default::default(),
             difficulty_beatmaps: Default::default(),
+            custom_data: Default::default(),
         }
     }
 }
 
 impl Info {
+    /// Notices shown to the player before playing the map, see
+    /// [`InfoCustomData::warnings`].
+    pub fn warnings(&self) -> &[String] {
+        &self.custom_data.warnings
+    }
+
+    /// Information shown to the player about the map, see
+    /// [`InfoCustomData::information`].
+    pub fn information(&self) -> &[String] {
+        &self.custom_data.information
+    }
+
+    /// Suggestions shown to the player about how to play the map, see
+    /// [`InfoCustomData::suggestions`].
+    pub fn suggestions(&self) -> &[String] {
+        &self.custom_data.suggestions
+    }
+
     /// Instantiates an [`Info`] from an info file, typically named `Info.dat`.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
-        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        parse_file(path.as_ref(), Self::from_slice)
+    }
+
+    /// Instantiates an [`Info`] from the contents of an info file, such as
+    /// data received over the network.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Serializes this [`Info`] to a file, typically named `Info.dat`.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer_pretty(&mut writer, self)?;
+
+        Ok(writer.flush()?)
+    }
+
+    /// Appends a [`DifficultyBeatmap`] to [`Info::difficulty_beatmaps`] and
+    /// returns an empty [`Beatmap`] to go along with it.
+    ///
+    /// The returned beatmap should be inserted into
+    /// [`crate::BeatSaberMap::beatmaps`] under the file stem of
+    /// [`DifficultyBeatmap::beatmap_data_filename`].
+    pub fn add_difficulty(&mut self, difficulty_beatmap: DifficultyBeatmap) -> Beatmap {
+        self.difficulty_beatmaps.push(difficulty_beatmap);
+
+        Beatmap::default()
+    }
+
+    /// The path of the song's audio file, given the map folder `dir`.
+    pub fn audio_path(&self, dir: impl AsRef<Path>) -> PathBuf {
+        dir.as_ref().join(&self.audio.song_filename)
+    }
+
+    /// The path of the audio file used for the in-game preview, given the map
+    /// folder `dir`.
+    pub fn preview_path(&self, dir: impl AsRef<Path>) -> PathBuf {
+        dir.as_ref().join(&self.song_preview_filename)
+    }
+
+    /// The start time and duration (in seconds) of the in-game preview.
+    ///
+    /// When [`Audio::preview_duration`] is `0.0`, the game plays a preview of
+    /// [`DEFAULT_PREVIEW_DURATION`] seconds instead, which is reflected here.
+    pub fn effective_preview(&self) -> (f64, f64) {
+        let duration = if self.audio.preview_duration > 0.0 {
+            self.audio.preview_duration
+        } else {
+            DEFAULT_PREVIEW_DURATION
+        };
+
+        (self.audio.preview_start_time, duration)
+    }
+
+    /// Every file this info file refers to, without duplicates, in the order
+    /// they are referenced.
+    pub fn referenced_files(&self) -> Vec<&Path> {
+        let mut files: Vec<&Path> = Vec::new();
+        let candidates = [
+            &self.audio.song_filename,
+            &self.song_preview_filename,
+            &self.cover_image_filename,
+            &self.audio.audio_data_filename,
+        ]
+        .into_iter()
+        .chain(self.difficulty_beatmaps.iter().flat_map(|beatmap| {
+            [
+                &beatmap.beatmap_data_filename,
+                &beatmap.lightshow_data_filename,
+            ]
+        }));
+
+        for file in candidates {
+            if !file.as_os_str().is_empty() && !files.contains(&file.as_path()) {
+                files.push(file);
+            }
+        }
+
+        files
+    }
+
+    /// Sorts [`Info::difficulty_beatmaps`] by characteristic, then by ascending
+    /// difficulty, for a consistent display order and stable file output.
+    ///
+    /// Characteristics are ordered as [`Characteristic`] declares them, with
+    /// [`Characteristic::Standard`] first and [`Characteristic::Custom`] last.
+    /// Difficulties with the same characteristic and difficulty keep their
+    /// original order.
+    pub fn sort_difficulties(&mut self) {
+        self.difficulty_beatmaps.sort_by(|a, b| {
+            (&a.characteristic, a.difficulty).cmp(&(&b.characteristic, b.difficulty))
+        });
+    }
+
+    /// Removes duplicate names from [`Info::environment_names`], keeping the
+    /// first of each, and updates [`DifficultyBeatmap::environment_name_idx`]
+    /// to point to the remaining name.
+    ///
+    /// Indices which are already out of bounds are left as they are.
+    pub fn dedup_environments(&mut self) {
+        let mut environment_names: Vec<String> = Vec::new();
+        let remapped: Vec<usize> = self
+            .environment_names
+            .drain(..)
+            .map(|name| {
+                environment_names
+                    .iter()
+                    .position(|existing| *existing == name)
+                    .unwrap_or_else(|| {
+                        environment_names.push(name);
+
+                        environment_names.len() - 1
+                    })
+            })
+            .collect();
+
+        self.environment_names = environment_names;
+
+        for difficulty_beatmap in &mut self.difficulty_beatmaps {
+            if let Some(&idx) = remapped.get(difficulty_beatmap.environment_name_idx) {
+                difficulty_beatmap.environment_name_idx = idx;
+            }
+        }
+    }
+
+    /// Checks that no difficulty in [`Info::difficulty_beatmaps`] uses the
+    /// same file for its beatmap and lightshow, and that no two difficulties
+    /// share a beatmap file, either of which makes loading the map confusing.
+    ///
+    /// Empty filenames are ignored.
+    pub fn validate_filenames(&self) -> Result<(), Vec<FilenameCollision>> {
+        let mut collisions = Vec::new();
+
+        for (i, difficulty_beatmap) in self.difficulty_beatmaps.iter().enumerate() {
+            let filename = &difficulty_beatmap.beatmap_data_filename;
+
+            if filename.as_os_str().is_empty() {
+                continue;
+            }
+
+            if *filename == difficulty_beatmap.lightshow_data_filename {
+                collisions.push(FilenameCollision::BeatmapIsLightshow {
+                    difficulty_beatmap: i,
+                    filename: filename.clone(),
+                });
+            }
+
+            if let Some(first) = self.difficulty_beatmaps[..i]
+                .iter()
+                .position(|other| other.beatmap_data_filename == *filename)
+            {
+                collisions.push(FilenameCollision::SharedBeatmap {
+                    first,
+                    second: i,
+                    filename: filename.clone(),
+                });
+            }
+        }
+
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(collisions)
+        }
+    }
+
+    /// Checks that no two difficulties in [`Info::difficulty_beatmaps`] share
+    /// a characteristic and difficulty, and that every
+    /// [`DifficultyBeatmap::environment_name_idx`] and
+    /// [`DifficultyBeatmap::beatmap_color_scheme_idx`] is in bounds.
+    ///
+    /// A color scheme index of `0` is allowed when there are no
+    /// [`Info::color_schemes`], since that is how maps without color schemes
+    /// are written. Every problem is returned, not just the first.
+    pub fn validate(&self) -> Result<(), InfoValidationError> {
+        let mut errors = Vec::new();
+
+        for (i, difficulty_beatmap) in self.difficulty_beatmaps.iter().enumerate() {
+            if let Some(first) = self.difficulty_beatmaps[..i].iter().position(|other| {
+                other.characteristic == difficulty_beatmap.characteristic
+                    && other.difficulty == difficulty_beatmap.difficulty
+            }) {
+                errors.push(InfoIssue::DuplicateDifficulty {
+                    first,
+                    second: i,
+                    characteristic: difficulty_beatmap.characteristic.clone(),
+                    difficulty: difficulty_beatmap.difficulty,
+                });
+            }
+
+            let environment_name_idx = difficulty_beatmap.environment_name_idx;
+
+            if environment_name_idx >= self.environment_names.len() {
+                errors.push(InfoIssue::EnvironmentOutOfBounds {
+                    difficulty_beatmap: i,
+                    index: environment_name_idx,
+                });
+            }
+
+            let color_scheme_idx = difficulty_beatmap.beatmap_color_scheme_idx;
+
+            if color_scheme_idx >= self.color_schemes.len()
+                && !(color_scheme_idx == 0 && self.color_schemes.is_empty())
+            {
+                errors.push(InfoIssue::ColorSchemeOutOfBounds {
+                    difficulty_beatmap: i,
+                    index: color_scheme_idx,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(InfoValidationError { errors })
+        }
+    }
+
+    /// The difficulties in [`Info::difficulty_beatmaps`] of a
+    /// [`Characteristic`], in the order they appear in the file.
+    pub fn beatmaps_for(
+        &self,
+        characteristic: Characteristic,
+    ) -> impl Iterator<Item = &DifficultyBeatmap> {
+        self.difficulty_beatmaps
+            .iter()
+            .filter(move |beatmap| beatmap.characteristic == characteristic)
+    }
+
+    /// Each [`Difficulty`] of a [`Characteristic`] in
+    /// [`Info::difficulty_beatmaps`], in ascending order no matter their order
+    /// in the file, without duplicates.
+    pub fn difficulties_for(&self, characteristic: Characteristic) -> Vec<Difficulty> {
+        let mut difficulties: Vec<_> = self
+            .beatmaps_for(characteristic)
+            .map(|beatmap| beatmap.difficulty)
+            .collect();
+
+        difficulties.sort();
+        difficulties.dedup();
+
+        difficulties
+    }
+
+    /// The lowest and highest [`Difficulty`] in [`Info::difficulty_beatmaps`],
+    /// optionally only considering those of a specific [`Characteristic`].
+    ///
+    /// Returns [`None`] if there are no matching difficulties.
+    pub fn difficulty_spread(
+        &self,
+        characteristic: Option<Characteristic>,
+    ) -> Option<(Difficulty, Difficulty)> {
+        let mut difficulties = self
+            .difficulty_beatmaps
+            .iter()
+            .filter(|beatmap| {
+                characteristic
+                    .as_ref()
+                    .is_none_or(|c| beatmap.characteristic == *c)
+            })
+            .map(|beatmap| beatmap.difficulty);
+        let first = difficulties.next()?;
+
+        Some(difficulties.fold((first, first), |(min, max), difficulty| {
+            (min.min(difficulty), max.max(difficulty))
+        }))
     }
 }
 
+/// Difficulties referring to files they should not, see
+/// [`Info::validate_filenames`].
+///
+/// Difficulties are given as indices into [`Info::difficulty_beatmaps`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FilenameCollision {
+    /// A difficulty uses the same file for its beatmap and lightshow.
+    #[error("Difficulty {difficulty_beatmap} uses `{}` as both beatmap and lightshow", filename.display())]
+    BeatmapIsLightshow {
+        /// The difficulty using the file.
+        difficulty_beatmap: usize,
+        /// The shared filename.
+        filename: PathBuf,
+    },
+    /// Two difficulties use the same beatmap file.
+    #[error("Difficulties {first} and {second} share the beatmap `{}`", filename.display())]
+    SharedBeatmap {
+        /// The earlier difficulty using the file.
+        first: usize,
+        /// The later difficulty using the file.
+        second: usize,
+        /// The shared filename.
+        filename: PathBuf,
+    },
+}
+
+/// A problem with the difficulties of an [`Info`], see [`Info::validate`].
+///
+/// Difficulties are given as indices into [`Info::difficulty_beatmaps`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum InfoIssue {
+    /// Two difficulties share a characteristic and difficulty.
+    #[error("Difficulties {first} and {second} are both {characteristic:?} {difficulty:?}")]
+    DuplicateDifficulty {
+        /// The earlier difficulty.
+        first: usize,
+        /// The later difficulty.
+        second: usize,
+        /// The shared characteristic.
+        characteristic: Characteristic,
+        /// The shared difficulty.
+        difficulty: Difficulty,
+    },
+    /// A difficulty's environment is not in [`Info::environment_names`].
+    #[error("Difficulty {difficulty_beatmap} uses environment {index}, which does not exist")]
+    EnvironmentOutOfBounds {
+        /// The difficulty using the environment.
+        difficulty_beatmap: usize,
+        /// The out of bounds index.
+        index: usize,
+    },
+    /// A difficulty's color scheme is not in [`Info::color_schemes`].
+    #[error("Difficulty {difficulty_beatmap} uses color scheme {index}, which does not exist")]
+    ColorSchemeOutOfBounds {
+        /// The difficulty using the color scheme.
+        difficulty_beatmap: usize,
+        /// The out of bounds index.
+        index: usize,
+    },
+}
+
+/// Every problem with the difficulties of an [`Info`], see
+/// [`Info::validate`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "Info has invalid difficulties: {}",
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+)]
+pub struct InfoValidationError {
+    /// The problems found, of which there is at least one.
+    pub errors: Vec<InfoIssue>,
+}
+
 /// Describes basic metadata about the song.
 #[doc = bsmg_wiki!("info"#"song-metadata")]
 #[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
@@ -130,6 +556,7 @@ impl Default for Audio {
 #[serde(default)]
 pub struct ColorScheme {
     #[doc = bsmg_wiki!("info"#"color-schemes")]
+    #[serde(deserialize_with = "crate::lenient::deserialize_bool")]
     pub use_override: bool,
     /// The player-facing name of the color scheme.
     pub color_scheme_name: String,
@@ -154,6 +581,130 @@ pub struct ColorScheme {
     /// Boosted variant of the second environment color.
     #[serde(with = "super::hex")]
     pub environment_color_1_boost: u32,
+    /// Any fields not modeled by [`ColorScheme`], such as `colorSchemeId`,
+    /// preserved so that they survive a round-trip.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ColorScheme {
+    /// [`ColorScheme::saber_a_color`] split into its channels.
+    pub fn saber_a_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.saber_a_color)
+    }
+
+    /// [`ColorScheme::saber_b_color`] split into its channels.
+    pub fn saber_b_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.saber_b_color)
+    }
+
+    /// [`ColorScheme::obstacles_color`] split into its channels.
+    pub fn obstacles_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.obstacles_color)
+    }
+
+    /// [`ColorScheme::environment_color_0`] split into its channels.
+    pub fn environment_color_0_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.environment_color_0)
+    }
+
+    /// [`ColorScheme::environment_color_1`] split into its channels.
+    pub fn environment_color_1_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.environment_color_1)
+    }
+
+    /// [`ColorScheme::environment_color_0_boost`] split into its channels.
+    pub fn environment_color_0_boost_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.environment_color_0_boost)
+    }
+
+    /// [`ColorScheme::environment_color_1_boost`] split into its channels.
+    pub fn environment_color_1_boost_rgba(&self) -> Rgba {
+        Rgba::from_u32(self.environment_color_1_boost)
+    }
+}
+
+/// A color as written in v2 map files, with channels ranging from 0.0 to 1.0.
+///
+/// Converts into the packed RGBA representation used by [`ColorScheme`].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct V2Color {
+    /// The red channel.
+    pub r: f64,
+    /// The green channel.
+    pub g: f64,
+    /// The blue channel.
+    pub b: f64,
+    /// The alpha channel, fully opaque if absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a: Option<f64>,
+}
+
+impl From<V2Color> for u32 {
+    fn from(value: V2Color) -> Self {
+        let channel = |channel: f64| (channel.clamp(0.0, 1.0) * 255.0).round() as u32;
+
+        channel(value.r) << 24
+            | channel(value.g) << 16
+            | channel(value.b) << 8
+            | channel(value.a.unwrap_or(1.0))
+    }
+}
+
+/// The custom colors of a v2 difficulty, found in its `_customData`.
+///
+/// Converts into a [`ColorScheme`], where missing colors are left as their
+/// default, except for boosted environment colors, which fall back to their
+/// unboosted counterparts like they do in-game.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct V2ColorScheme {
+    /// The color of the left saber.
+    #[serde(rename = "_colorLeft")]
+    pub color_left: Option<V2Color>,
+    /// The color of the right saber.
+    #[serde(rename = "_colorRight")]
+    pub color_right: Option<V2Color>,
+    /// The first environment color.
+    #[serde(rename = "_envColorLeft")]
+    pub env_color_left: Option<V2Color>,
+    /// The second environment color.
+    #[serde(rename = "_envColorRight")]
+    pub env_color_right: Option<V2Color>,
+    /// Boosted variant of the first environment color.
+    #[serde(rename = "_envColorLeftBoost")]
+    pub env_color_left_boost: Option<V2Color>,
+    /// Boosted variant of the second environment color.
+    #[serde(rename = "_envColorRightBoost")]
+    pub env_color_right_boost: Option<V2Color>,
+    /// The color of wall obstacles.
+    #[serde(rename = "_obstacleColor")]
+    pub obstacle_color: Option<V2Color>,
+}
+
+impl From<V2ColorScheme> for ColorScheme {
+    fn from(value: V2ColorScheme) -> Self {
+        let color = |color: Option<V2Color>| color.map(u32::from).unwrap_or_default();
+        let environment_color_0 = color(value.env_color_left);
+        let environment_color_1 = color(value.env_color_right);
+
+        Self {
+            use_override: true,
+            saber_a_color: color(value.color_left),
+            saber_b_color: color(value.color_right),
+            obstacles_color: color(value.obstacle_color),
+            environment_color_0,
+            environment_color_1,
+            environment_color_0_boost: value
+                .env_color_left_boost
+                .map_or(environment_color_0, u32::from),
+            environment_color_1_boost: value
+                .env_color_right_boost
+                .map_or(environment_color_1, u32::from),
+            ..Default::default()
+        }
+    }
 }
 
 /// An individual level associated with a map, organized by its characteristic
@@ -188,12 +739,148 @@ pub struct DifficultyBeatmap {
     /// The level file for non-interactable objects associated with the map.
     #[doc = bsmg_wiki!("info"#"beatmap-filename")]
     pub lightshow_data_filename: PathBuf,
+    /// See [`DifficultyCustomData`].
+    #[serde(skip_serializing_if = "DifficultyCustomData::is_empty")]
+    pub custom_data: DifficultyCustomData,
+}
+
+impl DifficultyBeatmap {
+    /// Starts building a [`DifficultyBeatmap`], see
+    /// [`DifficultyBeatmapBuilder`].
+    pub fn builder() -> DifficultyBeatmapBuilder {
+        DifficultyBeatmapBuilder::default()
+    }
+
+    /// The mods that must be installed to play this difficulty, see
+    /// [`DifficultyCustomData::requirements`].
+    pub fn requirements(&self) -> &[String] {
+        &self.custom_data.requirements
+    }
+
+    /// The mods that this difficulty is best played with, see
+    /// [`DifficultyCustomData::suggestions`].
+    pub fn suggestions(&self) -> &[String] {
+        &self.custom_data.suggestions
+    }
+
+    /// The number of beats it takes for an object to travel from where it
+    /// spawns to the player, given the song's `bpm`.
+    ///
+    /// This is the same calculation the game does, starting at 4 beats and
+    /// halving until the object travels no more than 18 units, before adding
+    /// [`DifficultyBeatmap::note_jump_start_beat_offset`] and clamping to a
+    /// minimum of a quarter beat.
+    pub fn half_jump_duration(&self, bpm: Beats) -> Beats {
+        half_jump_duration(
+            self.note_jump_movement_speed as f64,
+            bpm,
+            self.note_jump_start_beat_offset,
+        )
+    }
+
+    /// The distance (in units) an object travels from where it spawns to where
+    /// it despawns behind the player, given the song's `bpm`.
+    pub fn jump_distance(&self, bpm: Beats) -> f64 {
+        self.note_jump_movement_speed as f64 * 60.0 / bpm * self.half_jump_duration(bpm) * 2.0
+    }
+}
+
+/// Builds a [`DifficultyBeatmap`], leaving any field that isn't set as its
+/// default.
+///
+/// # Examples
+///
+/// ```
+/// use beat_saber_map::info::{Difficulty, DifficultyBeatmap};
+///
+/// let difficulty_beatmap = DifficultyBeatmap::builder()
+///     .difficulty(Difficulty::Expert)
+///     .note_jump_movement_speed(18)
+///     .beatmap_data_filename("Expert.dat")
+///     .build();
+///
+/// assert_eq!(difficulty_beatmap.difficulty, Difficulty::Expert);
+/// assert_eq!(difficulty_beatmap.environment_name_idx, 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DifficultyBeatmapBuilder {
+    difficulty_beatmap: DifficultyBeatmap,
+}
+
+impl DifficultyBeatmapBuilder {
+    /// Sets [`DifficultyBeatmap::characteristic`].
+    pub fn characteristic(mut self, characteristic: Characteristic) -> Self {
+        self.difficulty_beatmap.characteristic = characteristic;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::difficulty`].
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty_beatmap.difficulty = difficulty;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::beatmap_authors`].
+    pub fn beatmap_authors(mut self, beatmap_authors: BeatmapAuthors) -> Self {
+        self.difficulty_beatmap.beatmap_authors = beatmap_authors;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::environment_name_idx`].
+    pub fn environment_name_idx(mut self, environment_name_idx: usize) -> Self {
+        self.difficulty_beatmap.environment_name_idx = environment_name_idx;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::beatmap_color_scheme_idx`].
+    pub fn beatmap_color_scheme_idx(mut self, beatmap_color_scheme_idx: usize) -> Self {
+        self.difficulty_beatmap.beatmap_color_scheme_idx = beatmap_color_scheme_idx;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::note_jump_movement_speed`].
+    pub fn note_jump_movement_speed(mut self, note_jump_movement_speed: u32) -> Self {
+        self.difficulty_beatmap.note_jump_movement_speed = note_jump_movement_speed;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::note_jump_start_beat_offset`].
+    pub fn note_jump_start_beat_offset(mut self, note_jump_start_beat_offset: Beats) -> Self {
+        self.difficulty_beatmap.note_jump_start_beat_offset = note_jump_start_beat_offset;
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::beatmap_data_filename`].
+    pub fn beatmap_data_filename(mut self, beatmap_data_filename: impl Into<PathBuf>) -> Self {
+        self.difficulty_beatmap.beatmap_data_filename = beatmap_data_filename.into();
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::lightshow_data_filename`].
+    pub fn lightshow_data_filename(mut self, lightshow_data_filename: impl Into<PathBuf>) -> Self {
+        self.difficulty_beatmap.lightshow_data_filename = lightshow_data_filename.into();
+        self
+    }
+
+    /// Sets [`DifficultyBeatmap::custom_data`].
+    pub fn custom_data(mut self, custom_data: DifficultyCustomData) -> Self {
+        self.difficulty_beatmap.custom_data = custom_data;
+        self
+    }
+
+    /// Finishes building the [`DifficultyBeatmap`].
+    pub fn build(self) -> DifficultyBeatmap {
+        self.difficulty_beatmap
+    }
 }
 
 /// Groups [`DifficultyBeatmap`]s into unique categories and applies specialized
 /// behaviors to those affected [`DifficultyBeatmap`]s.
+///
+/// Characteristics are ordered as they are declared here, and
+/// [`Characteristic::Custom`] ones by name.
 #[doc = bsmg_wiki!("info"#"characteristic")]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize, Serialize)]
 pub enum Characteristic {
     /// No special behavior.
     #[default]
@@ -210,13 +897,84 @@ pub enum Characteristic {
     NinetyDegree,
     /// No special behavior.
     Legacy,
+    /// Has no restrictions on what patterns may be used.
+    Lawless,
+    /// Has no interactable objects, only a lightshow.
+    Lightshow,
+    /// A characteristic not known to this crate, such as one added by a mod.
+    ///
+    /// The original name is preserved so that it can be written back.
+    #[serde(untagged)]
+    Custom(String),
+}
+
+impl Characteristic {
+    /// The name of this characteristic as written in map files.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Standard => "Standard",
+            Self::NoArrows => "NoArrows",
+            Self::OneSaber => "OneSaber",
+            Self::ThreeSixtyDegree => "360Degree",
+            Self::NinetyDegree => "90Degree",
+            Self::Legacy => "Legacy",
+            Self::Lawless => "Lawless",
+            Self::Lightshow => "Lightshow",
+            Self::Custom(name) => name,
+        }
+    }
+}
+
+/// Formats the name of the characteristic as shown to players, such as
+/// "360 Degree", which is not necessarily the name written in map files, see
+/// [`Characteristic::as_str`].
+impl fmt::Display for Characteristic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Standard => "Standard",
+            Self::NoArrows => "No Arrows",
+            Self::OneSaber => "One Saber",
+            Self::ThreeSixtyDegree => "360 Degree",
+            Self::NinetyDegree => "90 Degree",
+            Self::Legacy => "Legacy",
+            Self::Lawless => "Lawless",
+            Self::Lightshow => "Lightshow",
+            Self::Custom(name) => name,
+        })
+    }
+}
+
+/// Parses a characteristic as written in map files, such as "360Degree", or
+/// as shown to players, such as "360 Degree", ignoring case and spaces.
+/// "360" and "90" are also accepted.
+///
+/// Names not known to this crate are rejected rather than parsed as
+/// [`Characteristic::Custom`], so that typos are caught.
+impl FromStr for Characteristic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.replace(' ', "").to_ascii_lowercase();
+
+        match normalized.as_str() {
+            "standard" => Ok(Self::Standard),
+            "noarrows" => Ok(Self::NoArrows),
+            "onesaber" => Ok(Self::OneSaber),
+            "360degree" | "360" => Ok(Self::ThreeSixtyDegree),
+            "90degree" | "90" => Ok(Self::NinetyDegree),
+            "legacy" => Ok(Self::Legacy),
+            "lawless" => Ok(Self::Lawless),
+            "lightshow" => Ok(Self::Lightshow),
+            _ => Err(Error::CharacteristicFromStr(s.to_string())),
+        }
+    }
 }
 
 /// A cosmetic label to indicate the overall difficulty of a
 /// [`DifficultyBeatmap`], relative to its [`Characteristic`].
 #[doc = bsmg_wiki!("info"#"difficulty")]
 #[allow(missing_docs)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize, Serialize)]
 pub enum Difficulty {
     Easy,
     #[default]
@@ -226,6 +984,140 @@ pub enum Difficulty {
     ExpertPlus,
 }
 
+impl Difficulty {
+    /// The name of this difficulty as written in map files.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+            Self::Expert => "Expert",
+            Self::ExpertPlus => "ExpertPlus",
+        }
+    }
+
+    /// The next harder difficulty, or [`None`] if this is
+    /// [`Difficulty::ExpertPlus`].
+    pub fn next(&self) -> Option<Difficulty> {
+        match self {
+            Self::Easy => Some(Self::Normal),
+            Self::Normal => Some(Self::Hard),
+            Self::Hard => Some(Self::Expert),
+            Self::Expert => Some(Self::ExpertPlus),
+            Self::ExpertPlus => None,
+        }
+    }
+
+    /// The next easier difficulty, or [`None`] if this is
+    /// [`Difficulty::Easy`].
+    pub fn prev(&self) -> Option<Difficulty> {
+        match self {
+            Self::Easy => None,
+            Self::Normal => Some(Self::Easy),
+            Self::Hard => Some(Self::Normal),
+            Self::Expert => Some(Self::Hard),
+            Self::ExpertPlus => Some(Self::Expert),
+        }
+    }
+}
+
+/// Parses a difficulty as written in map files, such as "ExpertPlus", ignoring
+/// case. "Expert+" and "E+" are also accepted.
+impl FromStr for Difficulty {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Self::Easy),
+            "normal" => Ok(Self::Normal),
+            "hard" => Ok(Self::Hard),
+            "expert" => Ok(Self::Expert),
+            "expertplus" | "expert+" | "e+" => Ok(Self::ExpertPlus),
+            _ => Err(Error::DifficultyFromStr(s.to_string())),
+        }
+    }
+}
+
+/// Formats the name of the difficulty as shown to players, such as "Expert+",
+/// which is not necessarily the name written in map files, see
+/// [`Difficulty::as_str`].
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+            Self::Expert => "Expert",
+            Self::ExpertPlus => "Expert+",
+        })
+    }
+}
+
+/// Mod data of an [`Info`], stored under `customData`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InfoCustomData {
+    /// Notices shown to the player before playing the map, such as "play with
+    /// headphones".
+    ///
+    /// Also read from `_warnings`, as written by v2 maps.
+    #[serde(alias = "_warnings", skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Information shown to the player about the map.
+    ///
+    /// Also read from `_information`, as written by v2 maps.
+    #[serde(alias = "_information", skip_serializing_if = "Vec::is_empty")]
+    pub information: Vec<String>,
+    /// Suggestions shown to the player about how to play the map.
+    ///
+    /// Also read from `_suggestions`, as written by v2 maps.
+    #[serde(alias = "_suggestions", skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// Any fields not modeled by [`InfoCustomData`], preserved so that they
+    /// survive a round-trip.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl InfoCustomData {
+    /// Whether there is no custom data at all.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+            && self.information.is_empty()
+            && self.suggestions.is_empty()
+            && self.extra.is_empty()
+    }
+}
+
+/// Mod data of a [`DifficultyBeatmap`], stored under `customData`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DifficultyCustomData {
+    /// The mods that must be installed to play the difficulty, such as
+    /// `"Chroma"` or `"Noodle Extensions"`.
+    ///
+    /// Also read from `_requirements`, as written by v2 maps.
+    #[serde(alias = "_requirements", skip_serializing_if = "Vec::is_empty")]
+    pub requirements: Vec<String>,
+    /// The mods that the difficulty is best played with, but which are not
+    /// required.
+    ///
+    /// Also read from `_suggestions`, as written by v2 maps.
+    #[serde(alias = "_suggestions", skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// Any fields not modeled by [`DifficultyCustomData`], preserved so that
+    /// they survive a round-trip.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl DifficultyCustomData {
+    /// Whether there is no custom data at all.
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty() && self.suggestions.is_empty() && self.extra.is_empty()
+    }
+}
+
 /// The designer(s) of a [`DifficultyBeatmap`], including any contributing
 /// mappers and lighters.
 #[doc = bsmg_wiki!("info"#"beatmap-authors")]
@@ -281,6 +1173,7 @@ mod tests {
                 environment_color_1: 0x30ACFFFF,
                 environment_color_0_boost: 0xD216D9FF,
                 environment_color_1_boost: 0x00FFA5FF,
+                extra: Default::default(),
             }],
             difficulty_beatmaps: vec![
                 DifficultyBeatmap {
@@ -296,6 +1189,7 @@ mod tests {
                     note_jump_start_beat_offset: 0.0,
                     beatmap_data_filename: "Easy.dat".into(),
                     lightshow_data_filename: "Lightshow.dat".into(),
+                    custom_data: Default::default(),
                 },
                 DifficultyBeatmap {
                     characteristic: Characteristic::Standard,
@@ -310,6 +1204,7 @@ mod tests {
                     note_jump_start_beat_offset: 0.0,
                     beatmap_data_filename: "Normal.dat".into(),
                     lightshow_data_filename: "Lightshow.dat".into(),
+                    custom_data: Default::default(),
                 },
                 DifficultyBeatmap {
                     characteristic: Characteristic::Standard,
@@ -324,6 +1219,7 @@ mod tests {
                     note_jump_start_beat_offset: 0.0,
                     beatmap_data_filename: "Hard.dat".into(),
                     lightshow_data_filename: "Lightshow.dat".into(),
+                    custom_data: Default::default(),
                 },
                 DifficultyBeatmap {
                     characteristic: Characteristic::Standard,
@@ -338,6 +1234,7 @@ mod tests {
                     note_jump_start_beat_offset: 1.0,
                     beatmap_data_filename: "Expert.dat".into(),
                     lightshow_data_filename: "Lightshow.dat".into(),
+                    custom_data: Default::default(),
                 },
                 DifficultyBeatmap {
                     characteristic: Characteristic::Standard,
@@ -352,8 +1249,10 @@ mod tests {
                     note_jump_start_beat_offset: 0.5,
                     beatmap_data_filename: "ExpertPlus.dat".into(),
                     lightshow_data_filename: "LightshowPlus.dat".into(),
+                    custom_data: Default::default(),
                 },
             ],
+            custom_data: Default::default(),
         }
     }
 
@@ -370,4 +1269,437 @@ mod tests {
 
         assert_eq!(deserialized, manual_recreation());
     }
+
+    #[test]
+    fn writes_default_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Info.dat");
+
+        Info::default().to_file(&path).unwrap();
+
+        assert_eq!(Info::from_file(&path).unwrap(), Info::default());
+    }
+
+    #[test]
+    fn preserves_unknown_color_scheme_fields() {
+        let json =
+            r##"{"colorSchemeName":"Weave","colorSchemeId":"Custom","saberAColor":"#C81414FF"}"##;
+        let color_scheme: ColorScheme = serde_json::from_str(json).unwrap();
+
+        assert_eq!(color_scheme.extra["colorSchemeId"], "Custom");
+
+        let reserialized = serde_json::to_value(&color_scheme).unwrap();
+
+        assert_eq!(reserialized["colorSchemeId"], "Custom");
+        assert_eq!(
+            serde_json::from_value::<ColorScheme>(reserialized).unwrap(),
+            color_scheme,
+        );
+    }
+
+    #[test]
+    fn accepts_string_use_override() {
+        let color_scheme: ColorScheme = serde_json::from_str(r#"{"useOverride":"true"}"#).unwrap();
+
+        assert!(color_scheme.use_override);
+        assert!(serde_json::from_str::<ColorScheme>(r#"{"useOverride":"yes"}"#).is_err());
+    }
+
+    #[test]
+    fn preserves_requirements_and_suggestions() {
+        let json = r#"{
+            "customData": {
+                "_requirements": ["Chroma", "Noodle Extensions"],
+                "suggestions": ["Cinema"],
+                "difficultyLabel": "Lawful"
+            }
+        }"#;
+        let difficulty_beatmap: DifficultyBeatmap = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            difficulty_beatmap.requirements(),
+            ["Chroma", "Noodle Extensions"]
+        );
+        assert_eq!(difficulty_beatmap.suggestions(), ["Cinema"]);
+
+        let reserialized = serde_json::to_value(&difficulty_beatmap).unwrap();
+
+        assert_eq!(
+            reserialized["customData"]["requirements"],
+            serde_json::json!(["Chroma", "Noodle Extensions"])
+        );
+        assert_eq!(reserialized["customData"]["difficultyLabel"], "Lawful");
+        assert!(serde_json::to_value(DifficultyBeatmap::default())
+            .unwrap()
+            .get("customData")
+            .is_none());
+    }
+
+    #[test]
+    fn preserves_player_notices() {
+        let json = r#"{
+            "customData": {
+                "_warnings": ["Play with headphones"],
+                "_information": ["Lights by Freeek"],
+                "_suggestions": ["Chroma"],
+                "_editors": {}
+            }
+        }"#;
+        let info: Info = serde_json::from_str(json).unwrap();
+
+        assert_eq!(info.warnings(), ["Play with headphones"]);
+        assert_eq!(info.information(), ["Lights by Freeek"]);
+        assert_eq!(info.suggestions(), ["Chroma"]);
+
+        let round_tripped: Info =
+            serde_json::from_str(&serde_json::to_string(&info).unwrap()).unwrap();
+
+        assert_eq!(round_tripped, info);
+        assert!(round_tripped.custom_data.extra.contains_key("_editors"));
+    }
+
+    #[test]
+    fn sorts_difficulties() {
+        let mut info = manual_recreation();
+
+        info.difficulty_beatmaps.reverse();
+        info.difficulty_beatmaps.swap(0, 2);
+        info.difficulty_beatmaps[1].characteristic = Characteristic::OneSaber;
+        info.sort_difficulties();
+
+        let sorted: Vec<_> = info
+            .difficulty_beatmaps
+            .iter()
+            .map(|difficulty_beatmap| {
+                (
+                    difficulty_beatmap.characteristic.clone(),
+                    difficulty_beatmap.difficulty,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            sorted,
+            [
+                (Characteristic::Standard, Difficulty::Easy),
+                (Characteristic::Standard, Difficulty::Normal),
+                (Characteristic::Standard, Difficulty::Hard),
+                (Characteristic::Standard, Difficulty::ExpertPlus),
+                (Characteristic::OneSaber, Difficulty::Expert),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_filename_collisions() {
+        let mut info = manual_recreation();
+
+        assert_eq!(info.validate_filenames(), Ok(()));
+
+        info.difficulty_beatmaps[1].lightshow_data_filename = "Normal.dat".into();
+        info.difficulty_beatmaps[3].beatmap_data_filename = "Easy.dat".into();
+
+        assert_eq!(
+            info.validate_filenames(),
+            Err(vec![
+                FilenameCollision::BeatmapIsLightshow {
+                    difficulty_beatmap: 1,
+                    filename: "Normal.dat".into(),
+                },
+                FilenameCollision::SharedBeatmap {
+                    first: 0,
+                    second: 3,
+                    filename: "Easy.dat".into(),
+                },
+            ]),
+        );
+    }
+
+    #[test]
+    fn computes_difficulty_spread() {
+        let info = manual_recreation();
+
+        assert_eq!(
+            info.difficulty_spread(None),
+            Some((Difficulty::Easy, Difficulty::ExpertPlus)),
+        );
+        assert_eq!(info.difficulty_spread(Some(Characteristic::OneSaber)), None);
+    }
+
+    #[test]
+    fn dedups_environments() {
+        let mut info = manual_recreation();
+
+        info.environment_names = ["Weave", "Origins", "Weave", "Origins", "BigMirror"]
+            .map(String::from)
+            .to_vec();
+
+        for (difficulty_beatmap, idx) in info.difficulty_beatmaps.iter_mut().zip([0, 1, 2, 4, 7]) {
+            difficulty_beatmap.environment_name_idx = idx;
+        }
+
+        info.dedup_environments();
+
+        assert_eq!(info.environment_names, ["Weave", "Origins", "BigMirror"]);
+        assert_eq!(
+            info.difficulty_beatmaps
+                .iter()
+                .map(|difficulty_beatmap| difficulty_beatmap.environment_name_idx)
+                .collect::<Vec<_>>(),
+            [0, 1, 0, 2, 7],
+        );
+    }
+
+    #[test]
+    fn computes_jump_timing() {
+        let info = manual_recreation();
+        let expert = &info.difficulty_beatmaps[3];
+
+        assert_eq!(expert.half_jump_duration(120.0), 3.0);
+        assert_eq!(expert.jump_distance(120.0), 48.0);
+        assert_eq!(expert.half_jump_duration(300.0), 5.0);
+
+        let easy = DifficultyBeatmap {
+            note_jump_start_beat_offset: -10.0,
+            ..info.difficulty_beatmaps[0].clone()
+        };
+
+        assert_eq!(easy.half_jump_duration(120.0), 0.25);
+    }
+
+    #[test]
+    fn navigates_difficulties() {
+        assert_eq!(Difficulty::Easy.prev(), None);
+        assert_eq!(Difficulty::Normal.next(), Some(Difficulty::Hard));
+        assert_eq!(Difficulty::ExpertPlus.next(), None);
+        assert_eq!(Difficulty::ExpertPlus.prev(), Some(Difficulty::Expert));
+    }
+
+    #[test]
+    fn converts_v2_colors() {
+        let custom_data = r#"{
+            "_colorLeft": { "r": 0.7843137, "g": 0.0784313, "b": 0.0784313 },
+            "_colorRight": { "r": 0.1568627, "g": 0.5568627, "b": 0.8235294, "a": 0.5 },
+            "_envColorLeft": { "r": 1, "g": 1, "b": 1 }
+        }"#;
+        let color_scheme =
+            ColorScheme::from(serde_json::from_str::<V2ColorScheme>(custom_data).unwrap());
+
+        assert_eq!(color_scheme.saber_a_color, 0xC81414FF);
+        assert_eq!(color_scheme.saber_b_color, 0x288ED280);
+        assert_eq!(color_scheme.environment_color_0, 0xFFFFFFFF);
+        assert_eq!(color_scheme.environment_color_0_boost, 0xFFFFFFFF);
+        assert_eq!(color_scheme.obstacles_color, 0);
+    }
+
+    #[test]
+    fn preserves_custom_characteristic() {
+        let characteristic: Characteristic = serde_json::from_str(r#""Sideways""#).unwrap();
+
+        assert_eq!(
+            characteristic,
+            Characteristic::Custom("Sideways".to_string())
+        );
+        assert_eq!(
+            serde_json::to_string(&characteristic).unwrap(),
+            r#""Sideways""#,
+        );
+        assert_eq!(
+            serde_json::from_str::<Characteristic>(r#""Lawless""#).unwrap(),
+            Characteristic::Lawless,
+        );
+        assert_eq!(
+            serde_json::to_string(&Characteristic::Lightshow).unwrap(),
+            r#""Lightshow""#,
+        );
+        assert_eq!(
+            serde_json::from_str::<Characteristic>(r#""360Degree""#).unwrap(),
+            Characteristic::ThreeSixtyDegree,
+        );
+    }
+
+    #[test]
+    fn deserializes_difficulty_beatmaps_object() {
+        let array = r#"{"difficultyBeatmaps":[{"difficulty":"Easy"},{"difficulty":"Hard"}]}"#;
+        let object =
+            r#"{"difficultyBeatmaps":{"a":{"difficulty":"Easy"},"b":{"difficulty":"Hard"}}}"#;
+        let from_array: Info = serde_json::from_str(array).unwrap();
+        let from_object: Info = serde_json::from_str(object).unwrap();
+
+        assert_eq!(from_array.difficulty_beatmaps.len(), 2);
+        assert_eq!(
+            from_object.difficulty_beatmaps,
+            from_array.difficulty_beatmaps
+        );
+    }
+
+    #[test]
+    fn resolves_audio_paths() {
+        let info = manual_recreation();
+
+        assert_eq!(info.audio_path("sample"), Path::new("sample/song.ogg"));
+        assert_eq!(info.preview_path("sample"), Path::new("sample/song.ogg"));
+    }
+
+    #[test]
+    fn defaults_preview_duration() {
+        let mut info = manual_recreation();
+
+        assert_eq!(info.effective_preview(), (0.0, DEFAULT_PREVIEW_DURATION));
+
+        info.audio.preview_start_time = 12.0;
+        info.audio.preview_duration = 5.0;
+
+        assert_eq!(info.effective_preview(), (12.0, 5.0));
+    }
+
+    #[test]
+    fn adds_difficulty() {
+        let mut info = Info::default();
+        let beatmap = info.add_difficulty(DifficultyBeatmap {
+            difficulty: Difficulty::Expert,
+            beatmap_data_filename: "Expert.dat".into(),
+            ..Default::default()
+        });
+
+        assert_eq!(info.difficulty_beatmaps.len(), 1);
+        assert_eq!(info.difficulty_beatmaps[0].difficulty, Difficulty::Expert);
+        assert_eq!(beatmap, Beatmap::default());
+    }
+
+    #[test]
+    fn validates_difficulties() {
+        let mut info = manual_recreation();
+
+        assert_eq!(info.validate(), Ok(()));
+
+        info.difficulty_beatmaps[1].characteristic =
+            info.difficulty_beatmaps[0].characteristic.clone();
+        info.difficulty_beatmaps[1].difficulty = info.difficulty_beatmaps[0].difficulty;
+        info.difficulty_beatmaps[1].environment_name_idx = 5;
+        info.color_schemes.clear();
+        info.difficulty_beatmaps[2].beatmap_color_scheme_idx = 1;
+
+        assert_eq!(
+            info.validate().unwrap_err().errors,
+            [
+                InfoIssue::DuplicateDifficulty {
+                    first: 0,
+                    second: 1,
+                    characteristic: info.difficulty_beatmaps[0].characteristic.clone(),
+                    difficulty: info.difficulty_beatmaps[0].difficulty,
+                },
+                InfoIssue::EnvironmentOutOfBounds {
+                    difficulty_beatmap: 1,
+                    index: 5,
+                },
+                InfoIssue::ColorSchemeOutOfBounds {
+                    difficulty_beatmap: 2,
+                    index: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_difficulties_for_characteristic() {
+        let mut info = manual_recreation();
+
+        info.difficulty_beatmaps.reverse();
+
+        assert_eq!(
+            info.beatmaps_for(Characteristic::Standard).count(),
+            info.difficulty_beatmaps.len()
+        );
+        assert_eq!(
+            info.difficulties_for(Characteristic::Standard),
+            [
+                Difficulty::Easy,
+                Difficulty::Normal,
+                Difficulty::Hard,
+                Difficulty::Expert,
+                Difficulty::ExpertPlus,
+            ]
+        );
+        assert!(info.difficulties_for(Characteristic::OneSaber).is_empty());
+    }
+
+    #[test]
+    fn round_trips_unknown_characteristic() {
+        let json = sample().replacen(
+            r#""characteristic": "Standard""#,
+            r#""characteristic": "Sideways""#,
+            1,
+        );
+        let info: Info = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            info.difficulty_beatmaps[0].characteristic,
+            Characteristic::Custom("Sideways".to_string())
+        );
+        assert_eq!(serde_json::to_string_pretty(&info).unwrap(), json);
+    }
+
+    #[test]
+    fn orders_difficulties_and_characteristics() {
+        assert!(Difficulty::Easy < Difficulty::ExpertPlus);
+        assert!(Characteristic::Standard < Characteristic::Lawless);
+        assert!(Characteristic::Lightshow < Characteristic::Custom("Sideways".to_string()));
+    }
+
+    #[test]
+    fn displays_difficulties_and_characteristics() {
+        assert_eq!(Difficulty::Easy.to_string(), "Easy");
+        assert_eq!(Difficulty::Normal.to_string(), "Normal");
+        assert_eq!(Difficulty::Hard.to_string(), "Hard");
+        assert_eq!(Difficulty::Expert.to_string(), "Expert");
+        assert_eq!(Difficulty::ExpertPlus.to_string(), "Expert+");
+        assert_eq!(Characteristic::Standard.to_string(), "Standard");
+        assert_eq!(Characteristic::NoArrows.to_string(), "No Arrows");
+        assert_eq!(Characteristic::OneSaber.to_string(), "One Saber");
+        assert_eq!(Characteristic::ThreeSixtyDegree.to_string(), "360 Degree");
+        assert_eq!(Characteristic::NinetyDegree.to_string(), "90 Degree");
+        assert_eq!(Characteristic::Legacy.to_string(), "Legacy");
+        assert_eq!(Characteristic::Lawless.to_string(), "Lawless");
+        assert_eq!(Characteristic::Lightshow.to_string(), "Lightshow");
+        assert_eq!(
+            Characteristic::Custom("Sideways".to_string()).to_string(),
+            "Sideways"
+        );
+    }
+
+    #[test]
+    fn parses_difficulties_and_characteristics() {
+        assert_eq!(
+            "expertplus".parse::<Difficulty>().unwrap(),
+            Difficulty::ExpertPlus
+        );
+        assert_eq!("E+".parse::<Difficulty>().unwrap(), Difficulty::ExpertPlus);
+        assert_eq!("Hard".parse::<Difficulty>().unwrap(), Difficulty::Hard);
+        assert!(matches!(
+            "Harder".parse::<Difficulty>(),
+            Err(Error::DifficultyFromStr(s)) if s == "Harder"
+        ));
+
+        assert_eq!(
+            "standard".parse::<Characteristic>().unwrap(),
+            Characteristic::Standard
+        );
+        assert_eq!(
+            "360".parse::<Characteristic>().unwrap(),
+            Characteristic::ThreeSixtyDegree
+        );
+        assert_eq!(
+            "90Degree".parse::<Characteristic>().unwrap(),
+            Characteristic::NinetyDegree
+        );
+        assert_eq!(
+            Characteristic::OneSaber
+                .to_string()
+                .parse::<Characteristic>()
+                .unwrap(),
+            Characteristic::OneSaber
+        );
+        assert!("Sideways".parse::<Characteristic>().is_err());
+    }
 }
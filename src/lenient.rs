@@ -0,0 +1,158 @@
+use std::fmt;
+
+use serde::{
+    de::{Unexpected, Visitor},
+    Deserialize, Deserializer,
+};
+
+const EXPECTING: &str = "an integer from 0 to 255";
+
+/// A [`u8`] that may also be deserialized from a whole-number float, such as
+/// `1.0`, which some editors write in place of integers.
+pub struct LenientU8(pub u8);
+
+struct LenientU8Visitor;
+
+impl<'de> Visitor<'de> for LenientU8Visitor {
+    type Value = LenientU8;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(EXPECTING)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        u8::try_from(v)
+            .map(LenientU8)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &EXPECTING))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        u8::try_from(v)
+            .map(LenientU8)
+            .map_err(|_| E::invalid_value(Unexpected::Signed(v), &EXPECTING))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if v.fract() == 0.0 && (0.0..=u8::MAX as f64).contains(&v) {
+            Ok(LenientU8(v as u8))
+        } else {
+            Err(E::invalid_value(Unexpected::Float(v), &EXPECTING))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LenientU8 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LenientU8Visitor)
+    }
+}
+
+const BOOL_EXPECTING: &str = "a boolean, 0 or 1, or \"true\" or \"false\"";
+
+struct LenientBoolVisitor;
+
+impl Visitor<'_> for LenientBoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(BOOL_EXPECTING)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(v)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(Unexpected::Unsigned(v), &BOOL_EXPECTING)),
+        }
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(Unexpected::Signed(v), &BOOL_EXPECTING)),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match v {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(E::invalid_value(Unexpected::Str(v), &BOOL_EXPECTING)),
+        }
+    }
+}
+
+/// Deserializes a [`bool`] that may also be written as `0` or `1`, or as the
+/// strings `"true"` or `"false"`, as some exporters do.
+///
+/// For use with `#[serde(deserialize_with = "...")]`.
+pub fn deserialize_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    deserializer.deserialize_any(LenientBoolVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_whole_floats() {
+        assert_eq!(serde_json::from_str::<LenientU8>("1").unwrap().0, 1);
+        assert_eq!(serde_json::from_str::<LenientU8>("1.0").unwrap().0, 1);
+    }
+
+    #[test]
+    fn rejects_fractional_floats() {
+        assert!(serde_json::from_str::<LenientU8>("1.5").is_err());
+        assert!(serde_json::from_str::<LenientU8>("-1").is_err());
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Flag(#[serde(deserialize_with = "deserialize_bool")] bool);
+
+    #[test]
+    fn accepts_bool_encodings() {
+        for json in ["true", "1", r#""true""#] {
+            assert!(serde_json::from_str::<Flag>(json).unwrap().0);
+        }
+
+        for json in ["false", "0", r#""false""#] {
+            assert!(!serde_json::from_str::<Flag>(json).unwrap().0);
+        }
+    }
+
+    #[test]
+    fn rejects_nonsense_bools() {
+        let error = serde_json::from_str::<Flag>(r#""yes""#).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains(r#"expected a boolean, 0 or 1, or "true" or "false""#));
+        assert!(serde_json::from_str::<Flag>("2").is_err());
+    }
+}
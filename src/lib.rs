@@ -76,17 +76,39 @@ macro_rules! version_doc {
 pub mod audio;
 #[macro_use]
 pub mod beatmap;
+#[cfg(feature = "chart")]
+pub mod chart;
+pub mod color;
 mod hex;
 #[macro_use]
 pub mod info;
-// #[macro_use]
-// pub mod lightshow;
+mod lenient;
+pub mod lightshow;
+#[cfg(feature = "ordered")]
+pub mod ordered;
+pub mod source;
+pub mod v1;
+pub mod v3;
 
-use std::{collections::HashMap, ffi::OsString, io, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
 
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
 use thiserror::Error;
 
-pub use self::{audio::Audio, beatmap::Beatmap, info::Info /* , lightshow::Lightshow */};
+pub use self::{audio::Audio, beatmap::Beatmap, info::Info, lightshow::Lightshow};
+
+use self::{
+    beatmap::IndexError,
+    info::{Characteristic, Difficulty},
+    source::MapSource,
+};
 
 /// This type represents the beats of a song as a measurement of time.
 pub type Beats = f64;
@@ -100,6 +122,83 @@ pub enum Error {
     /// Error from [`std::io`].
     #[error(transparent)]
     Io(#[from] io::Error),
+    /// Occurs when a map file is not valid JSON or does not match the
+    /// structure of its kind of map file.
+    #[error("Could not parse `{}`: {source}", file.display())]
+    Parse {
+        /// The path of the file, relative to the map folder when loading a
+        /// whole map.
+        file: PathBuf,
+        /// The error from [`serde_json`].
+        source: serde_json::Error,
+    },
+    /// Occurs when a file referenced by another map file does not exist, such
+    /// as a beatmap file listed in `Info.dat` that is missing from the map
+    /// folder.
+    #[error("Could not find `{}` referenced by {referenced_by}", path.display())]
+    FileNotFound {
+        /// The map file containing the reference.
+        referenced_by: &'static str,
+        /// The path that could not be found.
+        path: PathBuf,
+    },
+    /// Occurs when a map has no `Info.dat`, which unlike the files of
+    /// [`Error::FileNotFound`] is not referenced by another map file.
+    ///
+    /// Holds the path that was opened, such as `MyMap/Info.dat` when loading
+    /// the map folder `MyMap`, or just `Info.dat` for other [`MapSource`]s.
+    #[error("Could not find `{}`", .0.display())]
+    MissingFile(PathBuf),
+    /// Error from [`symphonia`], when an audio file cannot be decoded.
+    #[cfg(feature = "audio-decoding")]
+    #[error(transparent)]
+    AudioDecoding(#[from] symphonia::core::errors::Error),
+    /// Error from [`zip`], when a zip archive cannot be read.
+    #[cfg(feature = "zip")]
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    /// Occurs when [`Info::difficulty_beatmaps`] has no difficulty with the
+    /// requested characteristic and difficulty.
+    #[error("Could not find {characteristic:?} {difficulty:?} in Info.dat")]
+    DifficultyNotFound {
+        /// The requested characteristic.
+        characteristic: Characteristic,
+        /// The requested difficulty.
+        difficulty: Difficulty,
+    },
+    /// Occurs when [`load_component`] cannot tell which kind of map file a
+    /// file is.
+    #[error("Could not recognize `{}` as a map file", .0.display())]
+    UnknownComponent(PathBuf),
+    /// Occurs when a file is larger than [`LoadOptions::max_file_size`], in
+    /// which case it is not read.
+    #[error("`{}` is larger than the maximum of {max_file_size} bytes", path.display())]
+    FileTooLarge {
+        /// The path of the file.
+        path: PathBuf,
+        /// The maximum size of a file, in bytes.
+        max_file_size: u64,
+    },
+    /// Occurs when two different files would be given the same filename, see
+    /// [`BeatSaberMap::normalize_filenames`] and
+    /// [`BeatSaberMap::add_beatmap`].
+    #[error("More than one file would be named `{}`", .0.display())]
+    FilenameCollision(PathBuf),
+    /// Occurs when a region of [`Audio::bpm_data`] is invalid, see
+    /// [`Audio::validate_bpm_regions`].
+    #[error("BPM region {index} is invalid: {problem}")]
+    InvalidBpmRegion {
+        /// The index of the region in [`Audio::bpm_data`].
+        index: usize,
+        /// Why the region is invalid.
+        problem: audio::BpmRegionProblem,
+    },
+    /// Occurs when failing to parse a [`str`] as a [`Difficulty`].
+    #[error("Could not parse `{0}` as a difficulty")]
+    DifficultyFromStr(String),
+    /// Occurs when failing to parse a [`str`] as a [`Characteristic`].
+    #[error("Could not parse `{0}` as a characteristic")]
+    CharacteristicFromStr(String),
     /// Occurs when failing to convert [`u8`] to [`beatmap::LineIndex`].
     #[error("Could not convert u8 to LineIndex, expected integer from 0 to 3, got {0}")]
     LineIndexTryFromU8(u8),
@@ -121,6 +220,81 @@ pub enum Error {
     ExecutionTimeTryFromU8(u8),
 }
 
+/// Options for loading map files, see [`BeatSaberMap::from_dir_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// The largest a file may be, in bytes, before [`Error::FileTooLarge`] is
+    /// returned instead of reading it. [`None`] disables the limit.
+    ///
+    /// Defaults to [`LoadOptions::DEFAULT_MAX_FILE_SIZE`].
+    pub max_file_size: Option<u64>,
+}
+
+impl LoadOptions {
+    /// The default value of [`LoadOptions::max_file_size`], 256 MiB, which is
+    /// far larger than any real map file.
+    pub const DEFAULT_MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: Some(Self::DEFAULT_MAX_FILE_SIZE),
+        }
+    }
+}
+
+/// Any one file of a Beat Saber map, see [`load_component`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapFile {
+    /// An `Info.dat` file.
+    Info(Info),
+    /// A `BPMInfo.dat` file.
+    Audio(Audio),
+    /// A beatmap file.
+    Beatmap(Beatmap),
+    /// A lightshow file.
+    Lightshow(Lightshow),
+}
+
+/// Deserializes a map file of any kind.
+///
+/// The kind of file is determined by the fields present in it rather than by
+/// its filename, so this works no matter what the file is called.
+///
+/// # Errors
+///
+/// Returns [`Error::UnknownComponent`] if the file is not recognized as any
+/// kind of map file.
+///
+/// # Examples
+///
+/// ```
+/// use beat_saber_map::{load_component, MapFile};
+///
+/// let file = load_component("sample/BPMInfo.dat".as_ref()).unwrap();
+///
+/// assert!(matches!(file, MapFile::Audio(_)));
+/// ```
+pub fn load_component(path: &Path) -> Result<MapFile, Error> {
+    parse_file(path, |bytes| {
+        let value: serde_json::Value = serde_json::from_slice(bytes)?;
+        let has = |field| value.get(field).is_some();
+
+        if has("song") || has("difficultyBeatmaps") {
+            Ok(MapFile::Info(serde_json::from_value(value)?))
+        } else if has("bpmData") || has("songChecksum") || has("lufsData") {
+            Ok(MapFile::Audio(serde_json::from_value(value)?))
+        } else if has("colorNotes") || has("bombNotes") || has("obstacles") || has("_notes") {
+            Ok(MapFile::Beatmap(Beatmap::from_slice(bytes)?))
+        } else if has("basicEvents") || has("eventBoxGroups") || has("waypoints") {
+            Ok(MapFile::Lightshow(serde_json::from_value(value)?))
+        } else {
+            Err(Error::UnknownComponent(path.to_path_buf()))
+        }
+    })
+}
+
 /// A structural representation of a Beat Saber map folder.
 #[doc = bsmg_wiki!()]
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -137,48 +311,2099 @@ pub struct BeatSaberMap {
     ///
     /// See [`Beatmap`].
     pub beatmaps: HashMap<OsString, Beatmap>,
+    /// Any lightshow files that may exist, keyed by the file stem of
+    /// [`info::DifficultyBeatmap::lightshow_data_filename`].
+    ///
+    /// See [`Lightshow`].
+    pub lightshows: HashMap<OsString, Lightshow>,
 }
 
 impl BeatSaberMap {
+    /// Creates a map with a single empty [`Characteristic::Standard`]
+    /// [`Difficulty::Normal`] difficulty, ready to be written with
+    /// [`BeatSaberMap::to_dir`].
+    ///
+    /// The difficulty's beatmap and lightshow files are named following the
+    /// convention of [`BeatSaberMap::normalize_filenames`], and it's played in
+    /// `DefaultEnvironment`. The song file itself is not created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use beat_saber_map::BeatSaberMap;
+    ///
+    /// let map = BeatSaberMap::new("Hello World", 120.0);
+    ///
+    /// assert_eq!(map.info.song.title, "Hello World");
+    /// assert_eq!(map.full_difficulties().count(), 1);
+    /// ```
+    pub fn new(song_title: &str, bpm: Beats) -> Self {
+        let characteristic = Characteristic::Standard;
+        let difficulty = Difficulty::Normal;
+        let beatmap_filename = conventional_filename(&characteristic, difficulty, "");
+        let lightshow_filename = conventional_filename(&characteristic, difficulty, "Lightshow");
+
+        BeatSaberMap {
+            info: Info {
+                song: info::Song {
+                    title: song_title.to_string(),
+                    ..Default::default()
+                },
+                audio: info::Audio {
+                    bpm,
+                    ..Default::default()
+                },
+                environment_names: vec!["DefaultEnvironment".to_string()],
+                difficulty_beatmaps: vec![info::DifficultyBeatmap::builder()
+                    .characteristic(characteristic)
+                    .difficulty(difficulty)
+                    .note_jump_movement_speed(10)
+                    .beatmap_data_filename(&beatmap_filename)
+                    .lightshow_data_filename(&lightshow_filename)
+                    .build()],
+                ..Default::default()
+            },
+            audio: Audio::default(),
+            beatmaps: HashMap::from([(beatmap_key(&beatmap_filename), Beatmap::default())]),
+            lightshows: HashMap::from([(beatmap_key(&lightshow_filename), Lightshow::default())]),
+        }
+    }
+
     /// Deserializes the files in a map folder.
+    ///
+    /// Returns [`Error::MissingFile`] if the folder has no `Info.dat`, or
+    /// [`Error::FileNotFound`] if `Info.dat` references a file that does not
+    /// exist in the folder.
+    ///
+    /// With the `gzip` feature enabled, files compressed with a `.gz` suffix,
+    /// such as `Info.dat.gz`, are read when the uncompressed file is missing.
+    ///
+    /// Files are limited to the default [`LoadOptions::max_file_size`], see
+    /// [`BeatSaberMap::from_dir_with_options`] to change it.
     pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, Error> {
-        let info = Info::from_file(dir.as_ref().join("Info.dat"))?;
+        Self::from_dir_with_options(dir, &LoadOptions::default())
+    }
+
+    /// Deserializes the files in a map folder, like [`BeatSaberMap::from_dir`],
+    /// with the given [`LoadOptions`].
+    ///
+    /// Returns [`Error::FileTooLarge`] without reading a file if it is larger
+    /// than [`LoadOptions::max_file_size`].
+    pub fn from_dir_with_options(
+        dir: impl AsRef<Path>,
+        options: &LoadOptions,
+    ) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+
+        Self::from_source(source::Dir::new(dir, options)).map_err(|err| match err {
+            Error::MissingFile(path) => Error::MissingFile(dir.join(path)),
+            other => other,
+        })
+    }
+
+    /// Deserializes the files of a map in a zip archive, such as a map
+    /// downloaded from BeatSaver.
+    ///
+    /// Files are found regardless of the case of their names in the archive.
+    /// Returns [`Error::FileNotFound`] if `Info.dat` references a file that
+    /// the archive does not have.
+    ///
+    /// Files are limited to the default [`LoadOptions::max_file_size`], see
+    /// [`BeatSaberMap::from_zip_with_options`] to change it.
+    #[cfg(feature = "zip")]
+    pub fn from_zip(reader: impl io::Read + io::Seek) -> Result<Self, Error> {
+        Self::from_zip_with_options(reader, &LoadOptions::default())
+    }
+
+    /// Deserializes the files of a map in a zip archive, like
+    /// [`BeatSaberMap::from_zip`], with the given [`LoadOptions`].
+    ///
+    /// Returns [`Error::FileTooLarge`] if a file is larger than
+    /// [`LoadOptions::max_file_size`] once decompressed.
+    #[cfg(feature = "zip")]
+    pub fn from_zip_with_options(
+        reader: impl io::Read + io::Seek,
+        options: &LoadOptions,
+    ) -> Result<Self, Error> {
+        Self::from_source(source::ZipSource::with_options(reader, options)?)
+    }
+
+    /// Deserializes the files of a map from any [`MapSource`].
+    ///
+    /// Returns [`Error::MissingFile`] if the source does not have `Info.dat`,
+    /// or [`Error::FileNotFound`] if `Info.dat` references a file that the
+    /// source does not have.
+    pub fn from_source(source: impl MapSource) -> Result<Self, Error> {
+        let info = in_file(Info::from_slice(&read_info(&source)?), "Info.dat".as_ref())?;
+        let read_referenced =
+            |path: &Path| referenced_by_info(source.read(&path.to_string_lossy()), path);
         let mut beatmaps = HashMap::new();
+        let mut lightshows = HashMap::new();
 
         for beatmap in info.difficulty_beatmaps.iter() {
+            let beatmap_filename = &beatmap.beatmap_data_filename;
+
             beatmaps.insert(
-                beatmap
-                    .beatmap_data_filename
-                    .file_stem()
-                    .unwrap_or(beatmap.beatmap_data_filename.as_os_str())
-                    .to_os_string(),
-                Beatmap::from_file(dir.as_ref().join(&beatmap.beatmap_data_filename))?,
+                beatmap_key(beatmap_filename),
+                in_file(
+                    Beatmap::from_slice(&read_referenced(beatmap_filename)?),
+                    beatmap_filename,
+                )?,
             );
+
+            let lightshow_filename = &beatmap.lightshow_data_filename;
+            let key = beatmap_key(lightshow_filename);
+
+            if !lightshow_filename.as_os_str().is_empty() && !lightshows.contains_key(&key) {
+                lightshows.insert(
+                    key,
+                    in_file(
+                        Lightshow::from_slice(&read_referenced(lightshow_filename)?),
+                        lightshow_filename,
+                    )?,
+                );
+            }
         }
 
+        let audio_filename = &info.audio.audio_data_filename;
+
         Ok(BeatSaberMap {
-            audio: Audio::from_file(dir.as_ref().join(&info.audio.audio_data_filename))?,
+            audio: in_file(
+                Audio::from_slice(&read_referenced(audio_filename)?),
+                audio_filename,
+            )?,
             info,
             beatmaps,
+            lightshows,
         })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+    /// Serializes the whole map into a single JSON value.
+    ///
+    /// The value is an object with `info`, `audio`, `beatmaps`, and
+    /// `lightshows` keys, where `beatmaps` and `lightshows` are objects keyed
+    /// the same as [`BeatSaberMap::beatmaps`] and [`BeatSaberMap::lightshows`].
+    /// This is not a format understood by Beat Saber, it's intended for
+    /// sending a map as one document, see [`BeatSaberMap::from_bundle`].
+    pub fn to_bundle(&self) -> serde_json::Value {
+        let beatmaps: serde_json::Map<_, _> = self
+            .beatmaps
+            .iter()
+            .map(|(name, beatmap)| (name.to_string_lossy().into_owned(), json!(beatmap)))
+            .collect();
+        let lightshows: serde_json::Map<_, _> = self
+            .lightshows
+            .iter()
+            .map(|(name, lightshow)| (name.to_string_lossy().into_owned(), json!(lightshow)))
+            .collect();
 
-    use super::*;
+        json!({
+            "info": self.info,
+            "audio": self.audio,
+            "beatmaps": beatmaps,
+            "lightshows": lightshows,
+        })
+    }
 
-    #[test]
-    fn contains_beatmaps() {
-        let beatmaps = BeatSaberMap::from_dir("sample").unwrap().beatmaps;
+    /// Deserializes a map from a JSON value produced by
+    /// [`BeatSaberMap::to_bundle`].
+    pub fn from_bundle(bundle: serde_json::Value) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct Bundle {
+            info: Info,
+            audio: Audio,
+            beatmaps: HashMap<String, Beatmap>,
+            #[serde(default)]
+            lightshows: HashMap<String, Lightshow>,
+        }
 
-        assert!(beatmaps.contains_key(&OsString::from_str("Easy").unwrap()));
-        assert!(beatmaps.contains_key(&OsString::from_str("Normal").unwrap()));
-        assert!(beatmaps.contains_key(&OsString::from_str("Hard").unwrap()));
-        assert!(beatmaps.contains_key(&OsString::from_str("Expert").unwrap()));
-        assert!(beatmaps.contains_key(&OsString::from_str("ExpertPlus").unwrap()));
-        assert_eq!(beatmaps.len(), 5);
+        let bundle: Bundle = serde_json::from_value(bundle)?;
+
+        Ok(BeatSaberMap {
+            info: bundle.info,
+            audio: bundle.audio,
+            beatmaps: bundle
+                .beatmaps
+                .into_iter()
+                .map(|(name, beatmap)| (name.into(), beatmap))
+                .collect(),
+            lightshows: bundle
+                .lightshows
+                .into_iter()
+                .map(|(name, lightshow)| (name.into(), lightshow))
+                .collect(),
+        })
+    }
+
+    /// Computes the hash BeatSaver and leaderboards identify a map by, from
+    /// the files of a map as they are in `source`.
+    ///
+    /// The hash is the SHA-1 digest of the bytes of `Info.dat` followed by the
+    /// bytes of each difficulty's beatmap file, in the order of
+    /// [`Info::difficulty_beatmaps`], as lowercase hexadecimal. The original
+    /// files must be hashed to reproduce BeatSaver's hash, since deserializing
+    /// and serializing them again rarely gives the same bytes, see
+    /// [`BeatSaberMap::hash`].
+    ///
+    /// This is not the same as [`Audio::song_checksum`], which is a checksum
+    /// of only the song's audio file.
+    ///
+    /// Returns [`Error::FileNotFound`] if `Info.dat` references a beatmap
+    /// file that the source does not have.
+    #[cfg(feature = "hash")]
+    pub fn hash_source(source: impl MapSource) -> Result<String, Error> {
+        let mut hasher = sha1_smol::Sha1::new();
+        let info_bytes = read_info(&source)?;
+        let info = in_file(Info::from_slice(&info_bytes), "Info.dat".as_ref())?;
+
+        hasher.update(&info_bytes);
+
+        for difficulty_beatmap in &info.difficulty_beatmaps {
+            let path = &difficulty_beatmap.beatmap_data_filename;
+
+            hasher.update(&referenced_by_info(
+                source.read(&path.to_string_lossy()),
+                path,
+            )?);
+        }
+
+        Ok(hasher.digest().to_string())
+    }
+
+    /// Computes the hash the map would have once written with
+    /// [`BeatSaberMap::to_dir`], see [`BeatSaberMap::hash_source`].
+    ///
+    /// Difficulties without a beatmap in [`BeatSaberMap::beatmaps`] are not
+    /// written, so they are skipped.
+    #[cfg(feature = "hash")]
+    pub fn hash(&self) -> Result<String, Error> {
+        let mut hasher = sha1_smol::Sha1::new();
+
+        hasher.update(&serde_json::to_vec_pretty(&self.info)?);
+
+        for difficulty_beatmap in &self.info.difficulty_beatmaps {
+            let key = beatmap_key(&difficulty_beatmap.beatmap_data_filename);
+
+            if let Some(beatmap) = self.beatmaps.get(&key) {
+                let mut bytes = Vec::new();
+
+                beatmap.to_writer(&mut bytes)?;
+                hasher.update(&bytes);
+            }
+        }
+
+        Ok(hasher.digest().to_string())
+    }
+
+    /// Finds difficulties with notes placed after the end of the song.
+    ///
+    /// The beat of each difficulty's last note, including bombs, is converted to
+    /// seconds using the constant [BPM] and compared against the [song
+    /// duration]. Notes within half a second after the end of the song are
+    /// tolerated.
+    ///
+    /// [BPM]: info::Audio::bpm
+    /// [song duration]: info::Audio::song_duration
+    pub fn check_notes_within_song(&self) -> Vec<OutOfBoundsNote> {
+        let bpm = self.info.audio.bpm;
+
+        if bpm <= 0.0 {
+            return Vec::new();
+        }
+
+        self.info
+            .difficulty_beatmaps
+            .iter()
+            .filter_map(|difficulty_beatmap| {
+                let beatmap = self
+                    .beatmaps
+                    .get(&beatmap_key(&difficulty_beatmap.beatmap_data_filename))?;
+                let beat = beatmap
+                    .color_notes
+                    .iter()
+                    .chain(&beatmap.bomb_notes)
+                    .map(|note| note.beat)
+                    .max_by(f64::total_cmp)?;
+
+                (beat * 60.0 / bpm > self.info.audio.song_duration + 0.5).then(|| OutOfBoundsNote {
+                    characteristic: difficulty_beatmap.characteristic.clone(),
+                    difficulty: difficulty_beatmap.difficulty,
+                    beat,
+                })
+            })
+            .collect()
+    }
+
+    /// Every difficulty in [`Info::difficulty_beatmaps`] paired with its
+    /// beatmap and lightshow, looked up by filename.
+    ///
+    /// The lightshow is [`None`] if the difficulty has none or it's not in
+    /// [`BeatSaberMap::lightshows`]. Difficulties whose beatmap is not in
+    /// [`BeatSaberMap::beatmaps`] are skipped.
+    pub fn full_difficulties(
+        &self,
+    ) -> impl Iterator<Item = (&info::DifficultyBeatmap, &Beatmap, Option<&Lightshow>)> {
+        self.info
+            .difficulty_beatmaps
+            .iter()
+            .filter_map(|difficulty_beatmap| {
+                let beatmap = self
+                    .beatmaps
+                    .get(&beatmap_key(&difficulty_beatmap.beatmap_data_filename))?;
+                let lightshow = self
+                    .lightshows
+                    .get(&beatmap_key(&difficulty_beatmap.lightshow_data_filename));
+
+                Some((difficulty_beatmap, beatmap, lightshow))
+            })
+    }
+
+    /// Finds difficulties in [`Info::difficulty_beatmaps`] whose beatmap has
+    /// no color notes, which is almost always a mistake, such as a difficulty
+    /// that was never exported.
+    ///
+    /// Difficulties whose beatmap is not in [`BeatSaberMap::beatmaps`] are not
+    /// included, see [`BeatSaberMap::missing_files`] for those.
+    pub fn empty_difficulties(&self) -> Vec<(Characteristic, Difficulty)> {
+        self.full_difficulties()
+            .filter(|(_, beatmap, _)| beatmap.color_notes.is_empty())
+            .map(|(difficulty_beatmap, _, _)| {
+                (
+                    difficulty_beatmap.characteristic.clone(),
+                    difficulty_beatmap.difficulty,
+                )
+            })
+            .collect()
+    }
+
+    /// The timing a renderer needs to animate each of the color notes of a
+    /// difficulty flying towards the player, in the order of
+    /// [`Beatmap::color_notes`].
+    ///
+    /// Notes whose index is out of bounds are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DifficultyNotFound`] if there is no such difficulty,
+    /// or [`Error::FileNotFound`] if its beatmap is not in
+    /// [`BeatSaberMap::beatmaps`].
+    pub fn note_spawn_info(
+        &self,
+        characteristic: Characteristic,
+        difficulty: Difficulty,
+    ) -> Result<Vec<NoteSpawn>, Error> {
+        let (difficulty_beatmap, beatmap) = self.find_difficulty(characteristic, difficulty)?;
+        let bpm = self.info.audio.bpm;
+        let half_jump_duration = difficulty_beatmap.half_jump_duration(bpm);
+        let jump_distance = difficulty_beatmap.jump_distance(bpm);
+
+        Ok(beatmap
+            .resolved_color_notes()
+            .map(|(note, data)| NoteSpawn {
+                grid_position: data.grid_position.clone(),
+                reach_beat: note.beat,
+                spawn_beat: note.beat - half_jump_duration,
+                jump_distance,
+            })
+            .collect())
+    }
+
+    /// Converts the objects of a difficulty into a simplified list of events
+    /// for importing into other rhythm games, see [`chart`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DifficultyNotFound`] if there is no such difficulty,
+    /// or [`Error::FileNotFound`] if its beatmap is not in
+    /// [`BeatSaberMap::beatmaps`].
+    #[cfg(feature = "chart")]
+    pub fn to_chart_events(
+        &self,
+        characteristic: Characteristic,
+        difficulty: Difficulty,
+    ) -> Result<Vec<chart::ChartEvent>, Error> {
+        let (_, beatmap) = self.find_difficulty(characteristic, difficulty)?;
+
+        Ok(chart::events(beatmap, |beat| {
+            let seconds = self.audio.beat_to_seconds(beat);
+
+            if seconds.is_nan() {
+                beat * 60.0 / self.info.audio.bpm
+            } else {
+                seconds
+            }
+        }))
+    }
+
+    /// The beatmap of a difficulty in [`BeatSaberMap::beatmaps`], keyed by the
+    /// file stem of [`info::DifficultyBeatmap::beatmap_data_filename`] just
+    /// like [`BeatSaberMap::from_dir`] does, or [`None`] if it's not loaded.
+    pub fn beatmap_for(&self, difficulty_beatmap: &info::DifficultyBeatmap) -> Option<&Beatmap> {
+        self.beatmaps
+            .get(&beatmap_key(&difficulty_beatmap.beatmap_data_filename))
+    }
+
+    /// Like [`BeatSaberMap::beatmap_for`], but returns a mutable reference so
+    /// that the beatmap can be edited in place.
+    pub fn beatmap_for_mut(
+        &mut self,
+        difficulty_beatmap: &info::DifficultyBeatmap,
+    ) -> Option<&mut Beatmap> {
+        self.beatmaps
+            .get_mut(&beatmap_key(&difficulty_beatmap.beatmap_data_filename))
+    }
+
+    /// Adds a difficulty to [`Info::difficulty_beatmaps`] along with its
+    /// beatmap in [`BeatSaberMap::beatmaps`], returning the difficulty so that
+    /// it can be adjusted further.
+    ///
+    /// The beatmap file is named following the convention of
+    /// [`BeatSaberMap::normalize_filenames`], and the difficulty has no
+    /// lightshow file. If the difficulty already exists, only its beatmap is
+    /// replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FilenameCollision`] without adding anything if
+    /// another difficulty's beatmap or lightshow file has the name the beatmap
+    /// would be written to, since it would be replaced too.
+    pub fn add_beatmap(
+        &mut self,
+        characteristic: Characteristic,
+        difficulty: Difficulty,
+        beatmap: Beatmap,
+    ) -> Result<&mut info::DifficultyBeatmap, Error> {
+        let difficulty_beatmaps = &mut self.info.difficulty_beatmaps;
+        let index = difficulty_beatmaps.iter().position(|difficulty_beatmap| {
+            difficulty_beatmap.characteristic == characteristic
+                && difficulty_beatmap.difficulty == difficulty
+        });
+        let filename = match index {
+            Some(index) => difficulty_beatmaps[index].beatmap_data_filename.clone(),
+            None => conventional_filename(&characteristic, difficulty, ""),
+        };
+        let key = beatmap_key(&filename);
+        let collides = difficulty_beatmaps
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != index)
+            .flat_map(|(_, difficulty_beatmap)| {
+                [
+                    &difficulty_beatmap.beatmap_data_filename,
+                    &difficulty_beatmap.lightshow_data_filename,
+                ]
+            })
+            .any(|other| !other.as_os_str().is_empty() && beatmap_key(other) == key);
+
+        if collides {
+            return Err(Error::FilenameCollision(filename));
+        }
+
+        let index = match index {
+            Some(index) => index,
+            None => {
+                difficulty_beatmaps.push(
+                    info::DifficultyBeatmap::builder()
+                        .characteristic(characteristic)
+                        .difficulty(difficulty)
+                        .beatmap_data_filename(filename)
+                        .build(),
+                );
+                difficulty_beatmaps.len() - 1
+            }
+        };
+
+        self.beatmaps.insert(key, beatmap);
+
+        Ok(&mut difficulty_beatmaps[index])
+    }
+
+    /// Removes a difficulty from [`Info::difficulty_beatmaps`], returning its
+    /// beatmap, or [`None`] if there is no such difficulty or it has no
+    /// beatmap in [`BeatSaberMap::beatmaps`].
+    ///
+    /// Its beatmap and lightshow are only removed from
+    /// [`BeatSaberMap::beatmaps`] and [`BeatSaberMap::lightshows`] if no other
+    /// difficulty uses them.
+    pub fn remove_beatmap(
+        &mut self,
+        characteristic: Characteristic,
+        difficulty: Difficulty,
+    ) -> Option<Beatmap> {
+        let difficulty_beatmaps = &mut self.info.difficulty_beatmaps;
+        let index = difficulty_beatmaps.iter().position(|difficulty_beatmap| {
+            difficulty_beatmap.characteristic == characteristic
+                && difficulty_beatmap.difficulty == difficulty
+        })?;
+        let removed = difficulty_beatmaps.remove(index);
+        let key = beatmap_key(&removed.beatmap_data_filename);
+        let lightshow_key = beatmap_key(&removed.lightshow_data_filename);
+        let still_used = |key: &OsString, filename: fn(&info::DifficultyBeatmap) -> &PathBuf| {
+            difficulty_beatmaps
+                .iter()
+                .any(|other| beatmap_key(filename(other)) == *key)
+        };
+
+        if !still_used(&lightshow_key, |other| &other.lightshow_data_filename) {
+            self.lightshows.remove(&lightshow_key);
+        }
+
+        if still_used(&key, |other| &other.beatmap_data_filename) {
+            self.beatmaps.get(&key).cloned()
+        } else {
+            self.beatmaps.remove(&key)
+        }
+    }
+
+    /// Finds a difficulty in [`Info::difficulty_beatmaps`] along with its
+    /// beatmap.
+    fn find_difficulty(
+        &self,
+        characteristic: Characteristic,
+        difficulty: Difficulty,
+    ) -> Result<(&info::DifficultyBeatmap, &Beatmap), Error> {
+        let difficulty_beatmap = self
+            .info
+            .difficulty_beatmaps
+            .iter()
+            .find(|difficulty_beatmap| {
+                difficulty_beatmap.characteristic == characteristic
+                    && difficulty_beatmap.difficulty == difficulty
+            })
+            .ok_or(Error::DifficultyNotFound {
+                characteristic,
+                difficulty,
+            })?;
+        let beatmap = self
+            .beatmap_for(difficulty_beatmap)
+            .ok_or_else(|| Error::FileNotFound {
+                referenced_by: "Info.dat",
+                path: difficulty_beatmap.beatmap_data_filename.clone(),
+            })?;
+
+        Ok((difficulty_beatmap, beatmap))
+    }
+
+    /// Renames every beatmap and lightshow file to follow the
+    /// `{Characteristic}{Difficulty}.dat` convention, such as
+    /// `StandardExpertPlus.dat`.
+    ///
+    /// Lightshow files are named `{Characteristic}{Difficulty}Lightshow.dat`
+    /// after the first difficulty that uses them, so difficulties sharing a
+    /// lightshow file continue to do so. References in [`BeatSaberMap::info`]
+    /// and keys of [`BeatSaberMap::beatmaps`] and [`BeatSaberMap::lightshows`]
+    /// are updated accordingly.
+    ///
+    /// Returns a map of old filenames to new filenames, containing only the
+    /// files that were renamed, so that the files on disk can be renamed to
+    /// match.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FilenameCollision`] without renaming anything if two
+    /// different beatmap or lightshow files would be given the same name, such
+    /// as when two difficulties share a characteristic and difficulty.
+    pub fn normalize_filenames(&mut self) -> Result<HashMap<PathBuf, PathBuf>, Error> {
+        let mut renames: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+        for difficulty_beatmap in &self.info.difficulty_beatmaps {
+            let characteristic = &difficulty_beatmap.characteristic;
+            let difficulty = difficulty_beatmap.difficulty;
+
+            for (filename, suffix) in [
+                (&difficulty_beatmap.beatmap_data_filename, ""),
+                (&difficulty_beatmap.lightshow_data_filename, "Lightshow"),
+            ] {
+                if !filename.as_os_str().is_empty() {
+                    renames.entry(filename.clone()).or_insert_with(|| {
+                        conventional_filename(characteristic, difficulty, suffix)
+                    });
+                }
+            }
+        }
+
+        let mut new_keys = HashSet::new();
+
+        for new in renames.values() {
+            if !new_keys.insert(beatmap_key(new)) {
+                return Err(Error::FilenameCollision(new.clone()));
+            }
+        }
+
+        for difficulty_beatmap in &mut self.info.difficulty_beatmaps {
+            for filename in [
+                &mut difficulty_beatmap.beatmap_data_filename,
+                &mut difficulty_beatmap.lightshow_data_filename,
+            ] {
+                if let Some(new) = renames.get(filename) {
+                    filename.clone_from(new);
+                }
+            }
+        }
+
+        renames.retain(|old, new| old != new);
+
+        let moved: Vec<_> = renames
+            .iter()
+            .filter_map(|(old, new)| {
+                self.beatmaps
+                    .remove(&beatmap_key(old))
+                    .map(|beatmap| (beatmap_key(new), beatmap))
+            })
+            .collect();
+
+        self.beatmaps.extend(moved);
+
+        let moved: Vec<_> = renames
+            .iter()
+            .filter_map(|(old, new)| {
+                self.lightshows
+                    .remove(&beatmap_key(old))
+                    .map(|lightshow| (beatmap_key(new), lightshow))
+            })
+            .collect();
+
+        self.lightshows.extend(moved);
+
+        Ok(renames)
+    }
+
+    /// Lists files referenced by `info` that do not exist in `dir`.
+    pub fn missing_files(dir: impl AsRef<Path>, info: &Info) -> Vec<PathBuf> {
+        info.referenced_files()
+            .into_iter()
+            .filter(|file| !dir.as_ref().join(file).is_file())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    /// Lists files in `dir` that are not referenced by `info`, such as leftover
+    /// images or backups.
+    ///
+    /// `Info.dat` itself is never listed. The returned paths are joined with
+    /// `dir` and sorted.
+    pub fn unreferenced_files(dir: impl AsRef<Path>, info: &Info) -> Result<Vec<PathBuf>, Error> {
+        let referenced: Vec<_> = info
+            .referenced_files()
+            .into_iter()
+            .map(|file| dir.as_ref().join(file))
+            .collect();
+        let mut unreferenced = Vec::new();
+
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if !entry.file_type()?.is_file()
+                || entry.file_name().eq_ignore_ascii_case("Info.dat")
+                || referenced.contains(&path)
+            {
+                continue;
+            }
+
+            unreferenced.push(path);
+        }
+
+        unreferenced.sort();
+
+        Ok(unreferenced)
+    }
+
+    /// Checks whether a map folder is ready to be shipped, collecting every
+    /// problem found instead of returning the first one.
+    ///
+    /// This combines [`BeatSaberMap::missing_files`],
+    /// [`BeatSaberMap::unreferenced_files`], and (with the `hash` feature)
+    /// [`BeatSaberMap::hash_source`], and tries to deserialize every audio,
+    /// beatmap, and lightshow file referenced by `Info.dat`. If `Info.dat`
+    /// itself can't be deserialized, that is the only problem reported.
+    pub fn audit_folder(dir: impl AsRef<Path>) -> FolderAudit {
+        let dir = dir.as_ref();
+        let mut audit = FolderAudit::default();
+        let info = match Info::from_file(dir.join("Info.dat")) {
+            Ok(info) => info,
+            Err(error) => {
+                audit
+                    .failures
+                    .push((Some(PathBuf::from("Info.dat")), error));
+
+                return audit;
+            }
+        };
+
+        audit.info_version_supported = info.version.starts_with("4.");
+        audit.missing_files = Self::missing_files(dir, &info);
+
+        match Self::unreferenced_files(dir, &info) {
+            Ok(unreferenced_files) => {
+                audit.unreferenced_files = unreferenced_files
+                    .into_iter()
+                    .map(|file| {
+                        file.strip_prefix(dir)
+                            .map_or(file.clone(), Path::to_path_buf)
+                    })
+                    .collect();
+            }
+            Err(error) => audit.failures.push((None, error)),
+        }
+
+        #[cfg(feature = "hash")]
+        {
+            audit.hash = Self::hash_source(dir).ok();
+        }
+
+        let mut checked = Vec::new();
+        let mut check = |file: &PathBuf, load: fn(&Path) -> Result<(), Error>| {
+            if file.as_os_str().is_empty()
+                || checked.contains(file)
+                || audit.missing_files.contains(file)
+            {
+                return;
+            }
+
+            checked.push(file.clone());
+
+            if let Err(error) = load(&dir.join(file)) {
+                audit.failures.push((Some(file.clone()), error));
+            }
+        };
+
+        check(&info.audio.audio_data_filename, |path| {
+            Audio::from_file(path).map(drop)
+        });
+
+        for difficulty_beatmap in &info.difficulty_beatmaps {
+            check(&difficulty_beatmap.beatmap_data_filename, |path| {
+                Beatmap::from_file(path).map(drop)
+            });
+            check(&difficulty_beatmap.lightshow_data_filename, |path| {
+                Lightshow::from_file(path).map(drop)
+            });
+        }
+
+        audit
+    }
+
+    /// Sets the title, artist, and subtitle of the song in [`Info::song`].
+    ///
+    /// A subtitle of [`None`] clears any existing subtitle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use beat_saber_map::BeatSaberMap;
+    ///
+    /// let mut map = BeatSaberMap::from_dir("sample").unwrap();
+    ///
+    /// map.set_song("Magic (Remix)", "Jaroslav Beck", None);
+    ///
+    /// assert_eq!(map.info.song.title, "Magic (Remix)");
+    /// assert_eq!(map.info.song.author, "Jaroslav Beck");
+    /// assert_eq!(map.info.song.subtitle, "");
+    /// ```
+    pub fn set_song(
+        &mut self,
+        title: impl Into<String>,
+        artist: impl Into<String>,
+        subtitle: Option<String>,
+    ) {
+        self.info.song = info::Song {
+            title: title.into(),
+            subtitle: subtitle.unwrap_or_default(),
+            author: artist.into(),
+        };
+    }
+
+    /// Decodes the song's audio file to update the metadata that depends on
+    /// it, which must be kept in sync whenever the audio file changes.
+    ///
+    /// [`Audio::song_sample_count`], [`Audio::song_frequency`], and
+    /// [`info::Audio::song_duration`] are updated. If [`Audio::bpm_data`] has a
+    /// single region, it is moved to end on the last sample, with its last
+    /// beat moved along so that its BPM is unchanged. The BPM is taken from
+    /// [`info::Audio::bpm`] if the region was previously empty. Ogg Vorbis and
+    /// WAV audio files are supported.
+    ///
+    /// [`Audio::song_sample_count`]: audio::Audio::song_sample_count
+    /// [`Audio::song_frequency`]: audio::Audio::song_frequency
+    /// [`Audio::bpm_data`]: audio::Audio::bpm_data
+    #[cfg(feature = "audio-decoding")]
+    pub fn refresh_audio_metadata(&mut self, audio_bytes: &[u8]) -> Result<(), Error> {
+        use symphonia::core::{
+            codecs::DecoderOptions, errors::Error as SymphoniaError, formats::FormatOptions,
+            io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+        };
+
+        let stream = MediaSourceStream::new(
+            Box::new(io::Cursor::new(audio_bytes.to_vec())),
+            Default::default(),
+        );
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &Hint::new(),
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )?
+            .format;
+        let track = format
+            .default_track()
+            .ok_or(SymphoniaError::Unsupported("no audio track"))?;
+        let track_id = track.id;
+        let mut frequency = track.codec_params.sample_rate.unwrap_or_default();
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+        let mut sample_count = 0;
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = decoder.decode(&packet)?;
+
+            sample_count += decoded.frames();
+            frequency = decoded.spec().rate;
+        }
+
+        let old_frequency = self.audio.song_frequency;
+
+        self.audio.song_sample_count = sample_count as u32;
+        self.audio.song_frequency = frequency;
+        self.info.audio.song_duration = if frequency == 0 {
+            0.0
+        } else {
+            sample_count as f64 / frequency as f64
+        };
+
+        if let ([region], true) = (self.audio.bpm_data.as_mut_slice(), frequency != 0) {
+            let samples = region.end_index as f64 - region.start_index as f64;
+            let beats_per_second = if old_frequency != 0 && samples > 0.0 {
+                (region.end_beat - region.start_beat) / (samples / old_frequency as f64)
+            } else {
+                self.info.audio.bpm / 60.0
+            };
+
+            if old_frequency != 0 {
+                region.start_index = (region.start_index as f64 * frequency as f64
+                    / old_frequency as f64)
+                    .round() as usize;
+            }
+
+            region.start_index = region.start_index.min(sample_count);
+            region.end_index = sample_count;
+            region.end_beat = region.start_beat
+                + (region.end_index - region.start_index) as f64 / frequency as f64
+                    * beats_per_second;
+        }
+
+        Ok(())
+    }
+
+    /// Sets up loudness normalization so that the song plays at `target_lufs`,
+    /// returning the gain (in decibels) needed to reach it.
+    ///
+    /// `measured_lufs` is the integrated loudness of the song, as measured by
+    /// an audio pipeline. If it's [`None`], the loudness already in
+    /// [`info::Audio::lufs`] is used instead. The measured loudness is recorded
+    /// in [`info::Audio::lufs`], and [`Audio::lufs_data`] is replaced with a
+    /// single region spanning the whole song recording it too, since the game
+    /// computes its own gain from it. The target is only used to compute the
+    /// returned gain.
+    ///
+    /// [`Audio::lufs_data`]: audio::Audio::lufs_data
+    ///
+    /// Returns [`None`] without changing anything if no measurement is
+    /// available, which is when `measured_lufs` is [`None`] and
+    /// [`info::Audio::lufs`] is `0.0`, or the measurement is not finite.
+    pub fn set_loudness_target(
+        &mut self,
+        target_lufs: f64,
+        measured_lufs: Option<f64>,
+    ) -> Option<f64> {
+        let measured_lufs = measured_lufs
+            .or((self.info.audio.lufs != 0.0).then_some(self.info.audio.lufs))
+            .filter(|lufs| lufs.is_finite())?;
+
+        self.info.audio.lufs = measured_lufs;
+        self.audio.lufs_data = vec![audio::LufsData {
+            start_index: 0,
+            end_index: self.audio.song_sample_count as usize,
+            loudness: measured_lufs,
+        }];
+
+        Some(target_lufs - measured_lufs)
+    }
+
+    /// Serializes the map into a map folder, creating it if it doesn't exist.
+    ///
+    /// Beatmaps are written to the filenames referenced by
+    /// [`Info::difficulty_beatmaps`], beatmaps which are not referenced are
+    /// not written. See [`BeatSaberMap::prepare_for_write`] to check the map
+    /// beforehand.
+    pub fn to_dir(&self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        self.write_dir(dir.as_ref(), false)
+    }
+
+    /// Like [`BeatSaberMap::to_dir`], but gzip-compresses each file and adds a
+    /// `.gz` suffix to its name, such as `Info.dat.gz`.
+    ///
+    /// The game cannot load compressed maps, but [`BeatSaberMap::from_dir`]
+    /// can.
+    #[cfg(feature = "gzip")]
+    pub fn to_dir_compressed(&self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        self.write_dir(dir.as_ref(), true)
+    }
+
+    fn write_dir(&self, dir: &Path, compress: bool) -> Result<(), Error> {
+        fs::create_dir_all(dir)?;
+        write_file(&dir.join("Info.dat"), compress, |writer| {
+            Ok(serde_json::to_writer_pretty(writer, &self.info)?)
+        })?;
+        write_file(
+            &dir.join(&self.info.audio.audio_data_filename),
+            compress,
+            |writer| Ok(serde_json::to_writer_pretty(writer, &self.audio)?),
+        )?;
+
+        for difficulty_beatmap in &self.info.difficulty_beatmaps {
+            let filename = &difficulty_beatmap.beatmap_data_filename;
+
+            if let Some(beatmap) = self.beatmaps.get(&beatmap_key(filename)) {
+                write_file(&dir.join(filename), compress, |writer| {
+                    beatmap.to_writer(writer)
+                })?;
+            }
+
+            let filename = &difficulty_beatmap.lightshow_data_filename;
+
+            if let Some(lightshow) = self.lightshows.get(&beatmap_key(filename)) {
+                write_file(&dir.join(filename), compress, |writer| {
+                    Ok(serde_json::to_writer_pretty(writer, lightshow)?)
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the map is structurally sound and tidies it up in
+    /// preparation for [`BeatSaberMap::to_dir`].
+    ///
+    /// Every beatmap is checked for out of bounds indices, and every
+    /// difficulty in [`Info::difficulty_beatmaps`] is checked to have a
+    /// beatmap in [`BeatSaberMap::beatmaps`]. If there are no findings, every
+    /// beatmap is then sorted with [`Beatmap::sort`] and compacted with
+    /// [`Beatmap::compact`]. Otherwise, nothing is changed and every finding
+    /// is returned.
+    pub fn prepare_for_write(&mut self) -> Result<(), Vec<ValidationFinding>> {
+        let mut findings = Vec::new();
+        let mut names: Vec<_> = self.beatmaps.keys().collect();
+
+        names.sort();
+
+        for name in names {
+            findings.extend(self.beatmaps[name].index_errors().into_iter().map(|error| {
+                ValidationFinding::InvalidIndex {
+                    beatmap: name.clone(),
+                    error,
+                }
+            }));
+        }
+
+        for difficulty_beatmap in &self.info.difficulty_beatmaps {
+            let filename = &difficulty_beatmap.beatmap_data_filename;
+
+            if !self.beatmaps.contains_key(&beatmap_key(filename)) {
+                findings.push(ValidationFinding::MissingBeatmap {
+                    characteristic: difficulty_beatmap.characteristic.clone(),
+                    difficulty: difficulty_beatmap.difficulty,
+                    filename: filename.clone(),
+                });
+            }
+        }
+
+        if !findings.is_empty() {
+            return Err(findings);
+        }
+
+        for beatmap in self.beatmaps.values_mut() {
+            beatmap.sort();
+            beatmap
+                .compact()
+                .expect("indices should have already been validated");
+        }
+
+        Ok(())
+    }
+
+    /// Checks that serializing and deserializing each file of the map gives
+    /// back the same structure, which would not be the case if writing the map
+    /// would silently alter it.
+    ///
+    /// `Info.dat` is checked first, then the audio file, then beatmaps and
+    /// lightshows in order of their keys in [`BeatSaberMap::beatmaps`] and
+    /// [`BeatSaberMap::lightshows`]. The first divergence found
+    /// is returned.
+    pub fn verify_round_trip(&self) -> Result<(), RoundTripError> {
+        round_trip("Info.dat", &self.info)?;
+        round_trip(
+            &self.info.audio.audio_data_filename.to_string_lossy(),
+            &self.audio,
+        )?;
+
+        let mut names: Vec<&OsString> = self.beatmaps.keys().collect();
+
+        names.sort();
+
+        for name in names {
+            round_trip(&name.to_string_lossy(), &self.beatmaps[name])?;
+        }
+
+        let mut names: Vec<&OsString> = self.lightshows.keys().collect();
+
+        names.sort();
+
+        for name in names {
+            round_trip(&name.to_string_lossy(), &self.lightshows[name])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A problem that would cause [`BeatSaberMap::to_dir`] to write a broken map,
+/// see [`BeatSaberMap::prepare_for_write`].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValidationFinding {
+    /// A beatmap contains an out of bounds index.
+    #[error("Beatmap `{}` is invalid: {error}", beatmap.to_string_lossy())]
+    InvalidIndex {
+        /// The key of the beatmap in [`BeatSaberMap::beatmaps`].
+        beatmap: OsString,
+        /// The out of bounds index.
+        error: IndexError,
+    },
+    /// A difficulty has no corresponding beatmap in
+    /// [`BeatSaberMap::beatmaps`].
+    #[error("{characteristic:?} {difficulty:?} has no beatmap for `{}`", filename.display())]
+    MissingBeatmap {
+        /// The characteristic of the difficulty.
+        characteristic: Characteristic,
+        /// The difficulty.
+        difficulty: Difficulty,
+        /// The beatmap filename referenced by the difficulty.
+        filename: PathBuf,
+    },
+}
+
+/// A file of a map which does not survive being serialized and deserialized,
+/// see [`BeatSaberMap::verify_round_trip`].
+#[derive(Error, Debug)]
+pub enum RoundTripError {
+    /// The file could not be serialized or deserialized again.
+    #[error("`{file}` could not be round-tripped: {source}")]
+    SerdeJson {
+        /// The name of the file.
+        file: String,
+        /// The error from [`serde_json`].
+        source: serde_json::Error,
+    },
+    /// The file was deserialized differently than it was before.
+    #[error("`{file}` changed when round-tripped, at `{pointer}`")]
+    Changed {
+        /// The name of the file.
+        file: String,
+        /// A JSON pointer to the first difference, such as
+        /// `/colorNotes/0/b`, or an empty string if the difference is not
+        /// visible in JSON.
+        pointer: String,
+    },
+}
+
+/// The health of a map folder, see [`BeatSaberMap::audit_folder`].
+///
+/// Every path is relative to the map folder.
+#[derive(Debug, Default)]
+pub struct FolderAudit {
+    /// Files referenced by `Info.dat` that do not exist, see
+    /// [`BeatSaberMap::missing_files`].
+    pub missing_files: Vec<PathBuf>,
+    /// Files that are not referenced by `Info.dat`, see
+    /// [`BeatSaberMap::unreferenced_files`].
+    pub unreferenced_files: Vec<PathBuf>,
+    /// The hash of the map, or [`None`] if it couldn't be computed, see
+    /// [`BeatSaberMap::hash_source`].
+    #[cfg(feature = "hash")]
+    pub hash: Option<String>,
+    /// Whether [`Info::version`] is a version this crate supports.
+    pub info_version_supported: bool,
+    /// Files which could not be read or deserialized along with the error, or
+    /// [`None`] instead of a file if the folder itself could not be read.
+    pub failures: Vec<(Option<PathBuf>, Error)>,
+}
+
+impl FolderAudit {
+    /// Whether the map folder has no missing files, no files that failed to
+    /// deserialize, and a supported info version.
+    ///
+    /// Unreferenced files don't break a map, so they are not considered.
+    pub fn is_shippable(&self) -> bool {
+        self.missing_files.is_empty() && self.failures.is_empty() && self.info_version_supported
+    }
+}
+
+/// A note placed after the end of the song, see
+/// [`BeatSaberMap::check_notes_within_song`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutOfBoundsNote {
+    /// The characteristic of the difficulty containing the note.
+    pub characteristic: Characteristic,
+    /// The difficulty containing the note.
+    pub difficulty: Difficulty,
+    /// The beat the note is placed on.
+    pub beat: Beats,
+}
+
+/// When and where a note appears, see [`BeatSaberMap::note_spawn_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteSpawn {
+    /// Where on the grid the note is placed.
+    pub grid_position: beatmap::GridPosition,
+    /// The beat the note reaches the player on.
+    pub reach_beat: Beats,
+    /// The beat the note spawns on, which is [`NoteSpawn::reach_beat`] minus
+    /// the half jump duration.
+    pub spawn_beat: Beats,
+    /// The distance (in units) the note travels, see
+    /// [`info::DifficultyBeatmap::jump_distance`].
+    pub jump_distance: f64,
+}
+
+/// The key of a beatmap in [`BeatSaberMap::beatmaps`], which is the file stem
+/// of its filename.
+fn beatmap_key(beatmap_data_filename: &Path) -> OsString {
+    beatmap_data_filename
+        .file_stem()
+        .unwrap_or(beatmap_data_filename.as_os_str())
+        .to_os_string()
+}
+
+/// The filename of a difficulty's file following the
+/// `{Characteristic}{Difficulty}{suffix}.dat` convention, see
+/// [`BeatSaberMap::normalize_filenames`].
+fn conventional_filename(
+    characteristic: &Characteristic,
+    difficulty: Difficulty,
+    suffix: &str,
+) -> PathBuf {
+    format!(
+        "{}{}{suffix}.dat",
+        characteristic.as_str(),
+        difficulty.as_str()
+    )
+    .into()
+}
+
+/// Creates the file at `path` and writes to it with `write`, gzip-compressing
+/// it and adding a `.gz` suffix to its name if `compress` is true.
+#[cfg_attr(not(feature = "gzip"), allow(unused_variables))]
+fn write_file(
+    path: &Path,
+    compress: bool,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), Error>,
+) -> Result<(), Error> {
+    #[cfg(feature = "gzip")]
+    if compress {
+        let mut gz_path = path.as_os_str().to_os_string();
+
+        gz_path.push(".gz");
+
+        let mut encoder = flate2::write::GzEncoder::new(
+            BufWriter::new(fs::File::create(gz_path)?),
+            flate2::Compression::default(),
+        );
+
+        write(&mut encoder)?;
+        encoder.finish()?.flush()?;
+
+        return Ok(());
+    }
+
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+
+    write(&mut writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Serializes and deserializes `value` and checks that it is unchanged, see
+/// [`BeatSaberMap::verify_round_trip`].
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq>(
+    file: &str,
+    value: &T,
+) -> Result<(), RoundTripError> {
+    let serde_json_error = |source| RoundTripError::SerdeJson {
+        file: file.to_string(),
+        source,
+    };
+    let serialized = serde_json::to_value(value).map_err(serde_json_error)?;
+    let deserialized: T = serde_json::from_value(serialized.clone()).map_err(serde_json_error)?;
+
+    if deserialized == *value {
+        return Ok(());
+    }
+
+    let reserialized = serde_json::to_value(&deserialized).map_err(serde_json_error)?;
+
+    Err(RoundTripError::Changed {
+        file: file.to_string(),
+        pointer: first_difference(&serialized, &reserialized).unwrap_or_default(),
+    })
+}
+
+/// A JSON pointer to the first place `a` and `b` differ, or [`None`] if they
+/// are equal.
+fn first_difference(a: &serde_json::Value, b: &serde_json::Value) -> Option<String> {
+    use serde_json::Value;
+
+    match (a, b) {
+        (Value::Object(a_map), Value::Object(b_map)) => {
+            let escape = |key: &str| key.replace('~', "~0").replace('/', "~1");
+
+            for (key, a_value) in a_map {
+                match b_map.get(key) {
+                    Some(b_value) => {
+                        if let Some(pointer) = first_difference(a_value, b_value) {
+                            return Some(format!("/{}{pointer}", escape(key)));
+                        }
+                    }
+                    None => return Some(format!("/{}", escape(key))),
+                }
+            }
+
+            b_map
+                .keys()
+                .find(|key| !a_map.contains_key(*key))
+                .map(|key| format!("/{}", escape(key)))
+        }
+        (Value::Array(a_array), Value::Array(b_array)) if a_array.len() == b_array.len() => a_array
+            .iter()
+            .zip(b_array)
+            .enumerate()
+            .find_map(|(i, (a_value, b_value))| {
+                first_difference(a_value, b_value).map(|pointer| format!("/{i}{pointer}"))
+            }),
+        _ => (a != b).then(String::new),
+    }
+}
+
+/// Reads the file at `path`, checking its size against
+/// [`LoadOptions::max_file_size`] before reading it.
+pub(crate) fn read_file(path: &Path, options: &LoadOptions) -> Result<Vec<u8>, Error> {
+    let file = fs::File::open(path)?;
+
+    if let Some(max_file_size) = options.max_file_size {
+        if file.metadata()?.len() > max_file_size {
+            return Err(Error::FileTooLarge {
+                path: path.to_path_buf(),
+                max_file_size,
+            });
+        }
+    }
+
+    read_limited(file, path, options)
+}
+
+/// Reads the file at `path` with the default [`LoadOptions`] and parses it
+/// with `parse`, see [`in_file`].
+pub(crate) fn parse_file<T>(
+    path: &Path,
+    parse: impl FnOnce(&[u8]) -> Result<T, Error>,
+) -> Result<T, Error> {
+    in_file(parse(&read_file(path, &LoadOptions::default())?), path)
+}
+
+/// Converts an [`Error::SerdeJson`] from parsing the file at `path` into
+/// [`Error::Parse`], so that the error says which file it came from.
+pub(crate) fn in_file<T>(result: Result<T, Error>, path: &Path) -> Result<T, Error> {
+    result.map_err(|err| match err {
+        Error::SerdeJson(source) => Error::Parse {
+            file: path.to_path_buf(),
+            source,
+        },
+        other => other,
+    })
+}
+
+/// Reads all of `reader`, stopping with [`Error::FileTooLarge`] once more than
+/// [`LoadOptions::max_file_size`] bytes have been read.
+///
+/// This also guards against files whose size is not known upfront, such as
+/// compressed files.
+pub(crate) fn read_limited(
+    mut reader: impl Read,
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+
+    match options.max_file_size {
+        Some(max_file_size) => {
+            reader.take(max_file_size + 1).read_to_end(&mut bytes)?;
+
+            if bytes.len() as u64 > max_file_size {
+                return Err(Error::FileTooLarge {
+                    path: path.to_path_buf(),
+                    max_file_size,
+                });
+            }
+        }
+        None => {
+            reader.read_to_end(&mut bytes)?;
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Reads `Info.dat` from `source`, returning [`Error::MissingFile`] if it does
+/// not exist.
+fn read_info(source: &impl MapSource) -> Result<Vec<u8>, Error> {
+    source.read("Info.dat").map_err(|err| match err {
+        Error::Io(err) if err.kind() == io::ErrorKind::NotFound => {
+            Error::MissingFile("Info.dat".into())
+        }
+        other => other,
+    })
+}
+
+/// Converts a [`io::ErrorKind::NotFound`] error from reading a file referenced
+/// by `Info.dat` into [`Error::FileNotFound`].
+fn referenced_by_info<T>(result: Result<T, Error>, path: &Path) -> Result<T, Error> {
+    result.map_err(|err| match err {
+        Error::Io(err) if err.kind() == io::ErrorKind::NotFound => Error::FileNotFound {
+            referenced_by: "Info.dat",
+            path: path.to_path_buf(),
+        },
+        other => other,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, str::FromStr};
+
+    use super::*;
+
+    #[test]
+    fn contains_beatmaps() {
+        let beatmaps = BeatSaberMap::from_dir("sample").unwrap().beatmaps;
+
+        assert!(beatmaps.contains_key(&OsString::from_str("Easy").unwrap()));
+        assert!(beatmaps.contains_key(&OsString::from_str("Normal").unwrap()));
+        assert!(beatmaps.contains_key(&OsString::from_str("Hard").unwrap()));
+        assert!(beatmaps.contains_key(&OsString::from_str("Expert").unwrap()));
+        assert!(beatmaps.contains_key(&OsString::from_str("ExpertPlus").unwrap()));
+        assert_eq!(beatmaps.len(), 5);
+    }
+
+    #[test]
+    fn contains_lightshows() {
+        let lightshows = BeatSaberMap::from_dir("sample").unwrap().lightshows;
+
+        assert!(lightshows.contains_key(OsStr::new("Lightshow")));
+        assert!(lightshows.contains_key(OsStr::new("LightshowPlus")));
+        assert_eq!(lightshows.len(), 2);
+    }
+
+    #[test]
+    fn loads_components() {
+        let map = BeatSaberMap::from_dir("sample").unwrap();
+
+        assert_eq!(
+            load_component("sample/Info.dat".as_ref()).unwrap(),
+            MapFile::Info(map.info)
+        );
+        assert_eq!(
+            load_component("sample/BPMInfo.dat".as_ref()).unwrap(),
+            MapFile::Audio(map.audio)
+        );
+
+        for name in ["Easy", "Normal", "Hard", "Expert", "ExpertPlus"] {
+            assert_eq!(
+                load_component(format!("sample/{name}.dat").as_ref()).unwrap(),
+                MapFile::Beatmap(map.beatmaps[OsStr::new(name)].clone())
+            );
+        }
+
+        for name in ["Lightshow", "LightshowPlus"] {
+            assert_eq!(
+                load_component(format!("sample/{name}.dat").as_ref()).unwrap(),
+                MapFile::Lightshow(map.lightshows[OsStr::new(name)].clone())
+            );
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Unknown.dat");
+
+        fs::write(&path, r#"{"version":"4.0.0"}"#).unwrap();
+
+        assert!(matches!(
+            load_component(&path),
+            Err(Error::UnknownComponent(p)) if p == path
+        ));
+    }
+
+    #[test]
+    fn round_trips_bundle() {
+        let map = BeatSaberMap::from_dir("sample").unwrap();
+
+        assert_eq!(BeatSaberMap::from_bundle(map.to_bundle()).unwrap(), map);
+    }
+
+    #[test]
+    fn finds_notes_after_song() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        assert!(map.check_notes_within_song().is_empty());
+
+        map.info.audio.song_duration = 2.0;
+
+        let out_of_bounds = map.check_notes_within_song();
+
+        assert_eq!(out_of_bounds.len(), 5);
+        assert_eq!(out_of_bounds[0].difficulty, Difficulty::Easy);
+        assert_eq!(out_of_bounds[0].beat, 10.0);
+    }
+
+    #[test]
+    fn finds_empty_difficulties() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        assert!(map.empty_difficulties().is_empty());
+
+        map.beatmaps
+            .get_mut(OsStr::new("Hard"))
+            .unwrap()
+            .color_notes
+            .clear();
+
+        assert_eq!(
+            map.empty_difficulties(),
+            [(Characteristic::Standard, Difficulty::Hard)]
+        );
+    }
+
+    #[test]
+    fn joins_full_difficulties() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        assert_eq!(map.full_difficulties().count(), 5);
+        assert!(map
+            .full_difficulties()
+            .all(|(_, _, lightshow)| lightshow.is_some()));
+
+        map.beatmaps.remove(OsStr::new("Hard"));
+        map.lightshows.remove(OsStr::new("LightshowPlus"));
+
+        let difficulties: Vec<_> = map
+            .full_difficulties()
+            .map(|(difficulty_beatmap, _, lightshow)| {
+                (difficulty_beatmap.difficulty, lightshow.is_some())
+            })
+            .collect();
+
+        assert_eq!(
+            difficulties,
+            [
+                (Difficulty::Easy, true),
+                (Difficulty::Normal, true),
+                (Difficulty::Expert, true),
+                (Difficulty::ExpertPlus, false),
+            ],
+        );
+    }
+
+    #[cfg(feature = "chart")]
+    #[test]
+    fn converts_difficulty_to_chart_events() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+        let events = map
+            .to_chart_events(Characteristic::Standard, Difficulty::Normal)
+            .unwrap();
+
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0].seconds, map.audio.beat_to_seconds(10.0));
+        assert_eq!(events[0].kind, chart::ChartEventKind::Hit);
+
+        // Without BPM regions, beats are converted with the BPM from Info.dat.
+        map.audio.bpm_data.clear();
+
+        let events = map
+            .to_chart_events(Characteristic::Standard, Difficulty::Normal)
+            .unwrap();
+
+        assert_eq!(events[0].seconds, 10.0 * 60.0 / 208.0);
+        assert!(matches!(
+            map.to_chart_events(Characteristic::OneSaber, Difficulty::Normal),
+            Err(Error::DifficultyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn computes_note_spawn_info() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+        let spawns = map
+            .note_spawn_info(Characteristic::Standard, Difficulty::Expert)
+            .unwrap();
+        let expert = &map.info.difficulty_beatmaps[3];
+        let half_jump_duration = expert.half_jump_duration(map.info.audio.bpm);
+
+        assert_eq!(
+            spawns.len(),
+            map.beatmaps[OsStr::new("Expert")].color_notes.len()
+        );
+        assert_eq!(
+            spawns[0].spawn_beat,
+            spawns[0].reach_beat - half_jump_duration
+        );
+        assert_eq!(
+            spawns[0].jump_distance,
+            expert.jump_distance(map.info.audio.bpm)
+        );
+        assert!(matches!(
+            map.note_spawn_info(Characteristic::OneSaber, Difficulty::Expert),
+            Err(Error::DifficultyNotFound { .. })
+        ));
+
+        map.beatmaps.remove(OsStr::new("Expert"));
+
+        assert!(matches!(
+            map.note_spawn_info(Characteristic::Standard, Difficulty::Expert),
+            Err(Error::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn normalizes_filenames() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+        let easy = map.beatmaps[OsStr::new("Easy")].clone();
+
+        map.info.difficulty_beatmaps[1].beatmap_data_filename = "normal final (2).dat".into();
+        map.beatmaps.insert(
+            "normal final (2)".into(),
+            map.beatmaps[OsStr::new("Normal")].clone(),
+        );
+        map.beatmaps.remove(OsStr::new("Normal"));
+
+        let renames = map.normalize_filenames().unwrap();
+
+        assert_eq!(
+            renames[Path::new("normal final (2).dat")],
+            Path::new("StandardNormal.dat"),
+        );
+        assert_eq!(
+            renames[Path::new("Lightshow.dat")],
+            Path::new("StandardEasyLightshow.dat"),
+        );
+        assert_eq!(
+            renames[Path::new("LightshowPlus.dat")],
+            Path::new("StandardExpertPlusLightshow.dat"),
+        );
+        assert_eq!(renames.len(), 7);
+        assert_eq!(
+            map.info.difficulty_beatmaps[3].lightshow_data_filename,
+            Path::new("StandardEasyLightshow.dat"),
+        );
+        assert_eq!(map.beatmaps[OsStr::new("StandardEasy")], easy);
+        assert!(map.beatmaps.contains_key(OsStr::new("StandardNormal")));
+        assert_eq!(map.beatmaps.len(), 5);
+        assert!(map
+            .lightshows
+            .contains_key(OsStr::new("StandardEasyLightshow")));
+        assert_eq!(map.lightshows.len(), 2);
+    }
+
+    #[test]
+    fn fails_to_normalize_colliding_filenames() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        map.info.difficulty_beatmaps[1].difficulty = Difficulty::Easy;
+
+        let info = map.info.clone();
+
+        assert!(matches!(
+            map.normalize_filenames(),
+            Err(Error::FilenameCollision(path)) if path == Path::new("StandardEasy.dat"),
+        ));
+        assert_eq!(map.info, info);
+    }
+
+    #[test]
+    fn finds_missing_and_unreferenced_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = Info::from_file("sample/Info.dat").unwrap();
+
+        for file in [
+            "Info.dat",
+            "BPMInfo.dat",
+            "Easy.dat",
+            "Hard.dat",
+            "cover.png.bak",
+        ] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        assert_eq!(
+            BeatSaberMap::missing_files(&dir, &info),
+            [
+                "song.ogg",
+                "cover.png",
+                "Lightshow.dat",
+                "Normal.dat",
+                "Expert.dat",
+                "ExpertPlus.dat",
+                "LightshowPlus.dat",
+            ]
+            .map(PathBuf::from),
+        );
+        assert_eq!(
+            BeatSaberMap::unreferenced_files(&dir, &info).unwrap(),
+            [dir.path().join("cover.png.bak")],
+        );
+    }
+
+    #[test]
+    fn prepares_and_writes_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        map.prepare_for_write().unwrap();
+        map.to_dir(&dir).unwrap();
+
+        assert_eq!(BeatSaberMap::from_dir(&dir).unwrap(), map);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn round_trips_compressed_dir() {
+        let map = BeatSaberMap::from_dir("sample").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        map.to_dir_compressed(dir.path()).unwrap();
+
+        assert!(dir.path().join("Info.dat.gz").exists());
+        assert!(dir.path().join("Expert.dat.gz").exists());
+        assert!(!dir.path().join("Info.dat").exists());
+        assert_eq!(BeatSaberMap::from_dir(dir.path()).unwrap(), map);
+    }
+
+    #[test]
+    fn rejects_oversized_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Sparse, so this does not actually take up a gigabyte of disk space.
+        fs::File::create(dir.path().join("Info.dat"))
+            .unwrap()
+            .set_len(1024 * 1024 * 1024)
+            .unwrap();
+
+        match BeatSaberMap::from_dir(dir.path()) {
+            Err(Error::FileTooLarge {
+                path,
+                max_file_size,
+            }) => {
+                assert_eq!(path, dir.path().join("Info.dat"));
+                assert_eq!(max_file_size, LoadOptions::DEFAULT_MAX_FILE_SIZE);
+            }
+            other => panic!("expected Error::FileTooLarge, got {other:?}"),
+        }
+
+        let options = LoadOptions {
+            max_file_size: Some(64),
+        };
+
+        assert!(matches!(
+            BeatSaberMap::from_dir_with_options("sample", &options),
+            Err(Error::FileTooLarge { .. })
+        ));
+
+        let options = LoadOptions {
+            max_file_size: None,
+        };
+
+        assert_eq!(
+            BeatSaberMap::from_dir_with_options("sample", &options).unwrap(),
+            BeatSaberMap::from_dir("sample").unwrap()
+        );
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn hashes_map() {
+        let dir = Path::new("sample");
+
+        // Computed with `cat Info.dat Easy.dat Normal.dat Hard.dat Expert.dat
+        // ExpertPlus.dat | sha1sum` in the sample folder.
+        assert_eq!(
+            BeatSaberMap::hash_source(dir).unwrap(),
+            "38f4942467eebeaaf08be7bd41ac79287affe9e6"
+        );
+
+        let map = BeatSaberMap::from_dir(dir).unwrap();
+        let written = tempfile::tempdir().unwrap();
+
+        map.to_dir(written.path()).unwrap();
+
+        assert_eq!(
+            map.hash().unwrap(),
+            BeatSaberMap::hash_source(written.path()).unwrap()
+        );
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn reads_zip() {
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+
+        for entry in fs::read_dir("sample").unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.is_file() {
+                let name = path.file_name().unwrap().to_string_lossy().to_lowercase();
+
+                writer
+                    .start_file(name, zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                writer.write_all(&fs::read(path).unwrap()).unwrap();
+            }
+        }
+
+        let archive = writer.finish().unwrap();
+
+        assert_eq!(
+            BeatSaberMap::from_zip(archive.clone()).unwrap(),
+            BeatSaberMap::from_dir("sample").unwrap(),
+        );
+
+        let options = LoadOptions {
+            max_file_size: Some(16),
+        };
+
+        assert!(matches!(
+            BeatSaberMap::from_zip_with_options(archive, &options),
+            Err(Error::FileTooLarge { path, max_file_size: 16 }) if path == Path::new("Info.dat")
+        ));
+    }
+
+    #[test]
+    fn verifies_round_trip() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        map.verify_round_trip().unwrap();
+        map.beatmaps
+            .get_mut(OsStr::new("Hard"))
+            .unwrap()
+            .color_notes_data[1]
+            .custom_data = Some(serde_json::Value::Null);
+
+        match map.verify_round_trip() {
+            Err(RoundTripError::Changed { file, pointer }) => {
+                assert_eq!(file, "Hard");
+                assert_eq!(pointer, "/colorNotesData/1/customData");
+            }
+            other => panic!("expected RoundTripError::Changed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_unsound_map() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        map.beatmaps.remove(OsStr::new("Hard"));
+        map.beatmaps
+            .get_mut(OsStr::new("Easy"))
+            .unwrap()
+            .color_notes[0]
+            .metadata_index = 5;
+
+        let findings = map.prepare_for_write().unwrap_err();
+
+        assert_eq!(findings.len(), 2);
+        assert!(matches!(
+            &findings[0],
+            ValidationFinding::InvalidIndex { beatmap, .. } if beatmap == "Easy",
+        ));
+        assert!(matches!(
+            &findings[1],
+            ValidationFinding::MissingBeatmap {
+                difficulty: Difficulty::Hard,
+                ..
+            },
+        ));
+    }
+
+    #[cfg(feature = "audio-decoding")]
+    #[test]
+    fn refreshes_audio_metadata() {
+        let samples: u32 = 22050;
+        let mut wav = Vec::new();
+
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&44100u32.to_le_bytes());
+        wav.extend_from_slice(&(44100u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples * 2).to_le_bytes());
+        wav.resize(wav.len() + samples as usize * 2, 0);
+
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+        let beats = [0.0, 0.25, 10.0, 26.0];
+        let seconds = beats.map(|beat| map.audio.beat_to_seconds(beat));
+
+        map.refresh_audio_metadata(&wav).unwrap();
+
+        assert_eq!(map.audio.song_sample_count, samples);
+        assert_eq!(map.audio.song_frequency, 44100);
+        assert_eq!(map.audio.bpm_data[0].end_index, samples as usize);
+
+        for (beat, seconds) in beats.into_iter().zip(seconds) {
+            assert!((map.audio.beat_to_seconds(beat) - seconds).abs() < 1e-9);
+        }
+
+        assert_eq!(map.info.audio.song_duration, 0.5);
+        assert!(map.refresh_audio_metadata(b"not audio").is_err());
+    }
+
+    #[test]
+    fn sets_loudness_target() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+
+        map.info.audio.lufs = 0.0;
+
+        assert_eq!(map.set_loudness_target(-14.0, None), None);
+        assert_eq!(map.set_loudness_target(-14.0, Some(f64::NAN)), None);
+        assert_eq!(map.set_loudness_target(-14.0, Some(-8.5)), Some(-5.5));
+        assert_eq!(map.info.audio.lufs, -8.5);
+        assert_eq!(
+            map.audio.lufs_data,
+            [audio::LufsData {
+                start_index: 0,
+                end_index: 1149214,
+                loudness: -8.5,
+            }],
+        );
+        assert_eq!(map.set_loudness_target(-10.0, None), Some(-1.5));
+    }
+
+    #[test]
+    fn reports_dangling_reference() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            BeatSaberMap::from_dir(&dir),
+            Err(Error::MissingFile(path)) if path == dir.path().join("Info.dat")
+        ));
+
+        for file in [
+            "Info.dat",
+            "BPMInfo.dat",
+            "Easy.dat",
+            "Normal.dat",
+            "Hard.dat",
+            "Lightshow.dat",
+        ] {
+            fs::copy(Path::new("sample").join(file), dir.path().join(file)).unwrap();
+        }
+
+        match BeatSaberMap::from_dir(&dir) {
+            Err(Error::FileNotFound {
+                referenced_by,
+                path,
+            }) => {
+                assert_eq!(referenced_by, "Info.dat");
+                assert_eq!(path, Path::new("Expert.dat"));
+            }
+            other => panic!("expected Error::FileNotFound, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_file_that_failed_to_parse() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for entry in fs::read_dir("sample").unwrap() {
+            let entry = entry.unwrap();
+
+            fs::copy(entry.path(), dir.path().join(entry.file_name())).unwrap();
+        }
+
+        fs::write(dir.path().join("Hard.dat"), "{").unwrap();
+
+        match BeatSaberMap::from_dir(&dir) {
+            Err(Error::Parse { file, .. }) => assert_eq!(file, Path::new("Hard.dat")),
+            other => panic!("expected Error::Parse, got {other:?}"),
+        }
+        assert!(matches!(
+            Beatmap::from_file(dir.path().join("Hard.dat")),
+            Err(Error::Parse { file, .. }) if file == dir.path().join("Hard.dat")
+        ));
+    }
+
+    #[test]
+    fn audits_folder() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let audit = BeatSaberMap::audit_folder(dir.path());
+
+        assert!(!audit.is_shippable());
+        assert_eq!(audit.failures[0].0, Some(PathBuf::from("Info.dat")));
+
+        for entry in fs::read_dir("sample").unwrap() {
+            let entry = entry.unwrap();
+
+            fs::copy(entry.path(), dir.path().join(entry.file_name())).unwrap();
+        }
+
+        // The sample doesn't include its song or cover image.
+        fs::write(dir.path().join("song.ogg"), "").unwrap();
+        fs::write(dir.path().join("cover.png"), "").unwrap();
+
+        let audit = BeatSaberMap::audit_folder(dir.path());
+
+        assert!(audit.is_shippable(), "{audit:?}");
+        #[cfg(feature = "hash")]
+        assert_eq!(
+            audit.hash,
+            BeatSaberMap::hash_source(Path::new("sample")).ok()
+        );
+
+        let info = Info::from_file(dir.path().join("Info.dat")).unwrap();
+        let beatmap = &info.difficulty_beatmaps[0].beatmap_data_filename;
+
+        fs::write(dir.path().join(beatmap), "{").unwrap();
+        fs::remove_file(dir.path().join(&info.audio.audio_data_filename)).unwrap();
+        fs::write(dir.path().join("backup.dat"), "").unwrap();
+
+        let audit = BeatSaberMap::audit_folder(dir.path());
+
+        assert!(!audit.is_shippable());
+        assert_eq!(audit.missing_files, [PathBuf::from("BPMInfo.dat")]);
+        assert_eq!(audit.unreferenced_files, [PathBuf::from("backup.dat")]);
+        assert_eq!(audit.failures.len(), 1);
+        assert_eq!(audit.failures[0].0.as_ref(), Some(beatmap));
+    }
+
+    #[test]
+    fn creates_new_map() {
+        let map = BeatSaberMap::new("Hello World", 120.0);
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(map.info.validate(), Ok(()));
+        assert_eq!(map.info.audio.bpm, 120.0);
+
+        map.to_dir(dir.path()).unwrap();
+
+        assert!(dir.path().join("StandardNormal.dat").is_file());
+        assert_eq!(BeatSaberMap::from_dir(dir.path()).unwrap(), map);
+    }
+
+    #[test]
+    fn adds_and_removes_beatmaps() {
+        let mut map = BeatSaberMap::new("Hello World", 120.0);
+        let mut beatmap = Beatmap::default();
+
+        beatmap.color_notes.push(beatmap::Object::default());
+
+        let difficulty_beatmap = map
+            .add_beatmap(
+                Characteristic::OneSaber,
+                Difficulty::Expert,
+                beatmap.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            difficulty_beatmap.beatmap_data_filename,
+            Path::new("OneSaberExpert.dat")
+        );
+
+        difficulty_beatmap.note_jump_movement_speed = 16;
+
+        assert_eq!(map.info.difficulty_beatmaps.len(), 2);
+        assert_eq!(map.beatmaps[OsStr::new("OneSaberExpert")], beatmap);
+
+        map.add_beatmap(
+            Characteristic::OneSaber,
+            Difficulty::Expert,
+            Beatmap::default(),
+        )
+        .unwrap();
+
+        assert_eq!(map.info.difficulty_beatmaps.len(), 2);
+        assert_eq!(map.info.difficulty_beatmaps[1].note_jump_movement_speed, 16);
+        assert_eq!(
+            map.remove_beatmap(Characteristic::OneSaber, Difficulty::Expert),
+            Some(Beatmap::default())
+        );
+        assert_eq!(
+            map.remove_beatmap(Characteristic::OneSaber, Difficulty::Expert),
+            None
+        );
+        assert!(map
+            .remove_beatmap(Characteristic::Standard, Difficulty::Normal)
+            .is_some());
+        assert!(map.beatmaps.is_empty());
+        assert!(map.lightshows.is_empty());
+    }
+
+    #[test]
+    fn fails_to_add_beatmap_over_another_difficulty() {
+        let mut map = BeatSaberMap::new("Hello World", 120.0);
+
+        map.info.difficulty_beatmaps[0].beatmap_data_filename = "OneSaberHard.dat".into();
+
+        let info = map.info.clone();
+
+        assert!(matches!(
+            map.add_beatmap(Characteristic::OneSaber, Difficulty::Hard, Beatmap::default()),
+            Err(Error::FilenameCollision(path)) if path == Path::new("OneSaberHard.dat"),
+        ));
+        assert_eq!(map.info, info);
+    }
+
+    #[test]
+    fn finds_beatmap_for_difficulty() {
+        let mut map = BeatSaberMap::from_dir("sample").unwrap();
+        let expert = map.info.difficulty_beatmaps[3].clone();
+
+        assert_eq!(
+            map.beatmap_for(&expert),
+            map.beatmaps.get(OsStr::new("Expert"))
+        );
+
+        map.beatmap_for_mut(&expert).unwrap().color_notes.clear();
+
+        assert!(map.beatmaps[OsStr::new("Expert")].color_notes.is_empty());
+
+        map.beatmaps.clear();
+
+        assert_eq!(map.beatmap_for(&expert), None);
     }
 }
@@ -2,8 +2,632 @@
 //!
 //! See [`Lightshow`] to get started.
 
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    beatmap::{CutDirection, GridPosition, Timed},
+    parse_file, Beats, Error,
+};
+
 /// Similar to [`crate::Beatmap`], the lightshow file defines collections and
 /// associated metadata for all non-interactable beatmap items, such as
 /// environment objects and lighting effects.
 #[doc = bsmg_wiki!("lightshow")]
-pub struct Lightshow;
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct Lightshow {
+    #[doc = version_doc!()]
+    pub version: String,
+    /// See [`Event`].
+    #[doc = bsmg_wiki!("lightshow"#"basic-events")]
+    pub basic_events: Vec<Event>,
+    /// See [`BasicEventData`].
+    pub basic_events_data: Vec<BasicEventData>,
+    /// See [`Event`].
+    #[doc = bsmg_wiki!("lightshow"#"color-boost-events")]
+    pub color_boost_events: Vec<Event>,
+    /// See [`ColorBoostEventData`].
+    pub color_boost_events_data: Vec<ColorBoostEventData>,
+    /// See [`Event`].
+    #[doc = bsmg_wiki!("lightshow"#"waypoints")]
+    pub waypoints: Vec<Event>,
+    /// See [`WaypointData`].
+    pub waypoints_data: Vec<WaypointData>,
+    /// See [`BasicEventTypesWithKeywords`].
+    pub basic_event_types_with_keywords: BasicEventTypesWithKeywords,
+    /// See [`EventBoxGroup`].
+    #[doc = bsmg_wiki!("lightshow"#"event-box-groups")]
+    pub event_box_groups: Vec<EventBoxGroup>,
+    /// See [`IndexFilter`].
+    pub index_filters: Vec<IndexFilter>,
+    /// See [`LightColorEventBox`].
+    pub light_color_event_boxes: Vec<LightColorEventBox>,
+    /// See [`LightColorEvent`].
+    pub light_color_events: Vec<LightColorEvent>,
+    /// See [`LightRotationEventBox`].
+    pub light_rotation_event_boxes: Vec<LightRotationEventBox>,
+    /// See [`LightRotationEvent`].
+    pub light_rotation_events: Vec<LightRotationEvent>,
+    /// See [`LightTranslationEventBox`].
+    pub light_translation_event_boxes: Vec<LightTranslationEventBox>,
+    /// See [`LightTranslationEvent`].
+    pub light_translation_events: Vec<LightTranslationEvent>,
+    /// See [`FxEventBox`].
+    pub fx_event_boxes: Vec<FxEventBox>,
+    /// See [`FloatFxEvent`].
+    pub float_fx_events: Vec<FloatFxEvent>,
+    /// Whether [`Lightshow::basic_events`] should be used in environments
+    /// designed for [`Lightshow::event_box_groups`].
+    pub use_normal_events_as_compatible_events: bool,
+}
+
+impl Default for Lightshow {
+    fn default() -> Self {
+        Self {
+            version: "4.0.0".to_string(),
+            basic_events: Default::default(),
+            basic_events_data: Default::default(),
+            color_boost_events: Default::default(),
+            color_boost_events_data: Default::default(),
+            waypoints: Default::default(),
+            waypoints_data: Default::default(),
+            basic_event_types_with_keywords: Default::default(),
+            event_box_groups: Default::default(),
+            index_filters: Default::default(),
+            light_color_event_boxes: Default::default(),
+            light_color_events: Default::default(),
+            light_rotation_event_boxes: Default::default(),
+            light_rotation_events: Default::default(),
+            light_translation_event_boxes: Default::default(),
+            light_translation_events: Default::default(),
+            fx_event_boxes: Default::default(),
+            float_fx_events: Default::default(),
+            use_normal_events_as_compatible_events: Default::default(),
+        }
+    }
+}
+
+impl Lightshow {
+    /// Instantiates a [`Lightshow`] from a lightshow file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        parse_file(path.as_ref(), Self::from_slice)
+    }
+
+    /// Instantiates a [`Lightshow`] from the contents of a lightshow file, such as
+    /// data received over the network.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Serializes this [`Lightshow`] to a lightshow file.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer_pretty(&mut writer, self)?;
+
+        Ok(writer.flush()?)
+    }
+}
+
+/// The placement of an event.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Event {
+    /// The specific point in time, as determined by the [BPM] of the song, when
+    /// this event should occur.
+    ///
+    /// [BPM]: super::info::Audio::bpm
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The index of corresponding data in `*_data` of [`Lightshow`], or of
+    /// [`EventBoxGroup::event_boxes`] in [`EventBox::events`], where it refers
+    /// to the events collection matching the [`EventBoxGroup::group_type`].
+    #[serde(rename = "i")]
+    pub metadata_index: usize,
+}
+
+impl Timed for Event {
+    fn beat(&self) -> Beats {
+        self.beat
+    }
+}
+
+/// Data for a basic event, which controls lights and other effects of
+/// environments predating [`EventBoxGroup`]s.
+#[doc = bsmg_wiki!("lightshow"#"basic-events")]
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BasicEventData {
+    /// Which part of the environment is affected.
+    #[serde(rename = "t")]
+    pub event_type: i32,
+    /// The effect to apply, which depends on [`BasicEventData::event_type`].
+    #[serde(rename = "i")]
+    pub value: i32,
+    /// A modifier for the effect, such as the brightness of lights.
+    #[serde(rename = "f")]
+    pub float_value: f64,
+}
+
+/// Data for a color boost event, which switches the environment to and from
+/// the boosted colors of its color scheme.
+#[doc = bsmg_wiki!("lightshow"#"color-boost-events")]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ColorBoostEventData {
+    /// `1` to use boosted colors, or `0` to use regular colors.
+    #[serde(rename = "b")]
+    pub boost: u8,
+}
+
+/// Data for a waypoint, which affects the movement of some environments.
+#[doc = bsmg_wiki!("lightshow"#"waypoints")]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WaypointData {
+    /// See [`GridPosition`].
+    #[serde(flatten)]
+    pub grid_position: GridPosition,
+    /// The direction the waypoint points in.
+    #[serde(rename = "d")]
+    pub offset_direction: CutDirection,
+}
+
+/// Basic event types which are enabled by keywords, used by some environments
+/// for special effects.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BasicEventTypesWithKeywords {
+    /// See [`EventTypesForKeyword`].
+    #[serde(rename = "d")]
+    pub keywords: Vec<EventTypesForKeyword>,
+}
+
+/// The basic event types enabled by a keyword.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventTypesForKeyword {
+    /// The keyword.
+    #[serde(rename = "k")]
+    pub keyword: String,
+    /// The enabled [`BasicEventData::event_type`]s.
+    #[serde(rename = "e")]
+    pub event_types: Vec<i32>,
+}
+
+/// A group of lights in the environment, and the event boxes controlling them.
+#[doc = bsmg_wiki!("lightshow"#"event-box-groups")]
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventBoxGroup {
+    /// The specific point in time, as determined by the [BPM] of the song, when
+    /// the event boxes of this group should begin.
+    ///
+    /// [BPM]: super::info::Audio::bpm
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The ID of the group of lights in the environment.
+    #[serde(rename = "g")]
+    pub group_id: i32,
+    /// What the event boxes control, `1` for color, `2` for rotation, `3` for
+    /// translation, or `4` for FX.
+    #[serde(rename = "t")]
+    pub group_type: u8,
+    /// See [`EventBox`].
+    #[serde(rename = "e")]
+    pub event_boxes: Vec<EventBox>,
+}
+
+impl Timed for EventBoxGroup {
+    fn beat(&self) -> Beats {
+        self.beat
+    }
+}
+
+/// A set of events applied to the lights of an [`EventBoxGroup`] selected by
+/// an [`IndexFilter`].
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventBox {
+    /// The index of an [`IndexFilter`] in [`Lightshow::index_filters`].
+    #[serde(rename = "f")]
+    pub index_filter: usize,
+    /// The index of the event box in the collection matching the
+    /// [`EventBoxGroup::group_type`], such as
+    /// [`Lightshow::light_color_event_boxes`].
+    #[serde(rename = "e")]
+    pub event_box: usize,
+    /// See [`Event`].
+    #[serde(rename = "l")]
+    pub events: Vec<Event>,
+}
+
+/// Selects which lights of an [`EventBoxGroup`] are affected by an
+/// [`EventBox`].
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IndexFilter {
+    /// How many lights are grouped together.
+    #[serde(rename = "c")]
+    pub chunks: i32,
+    /// `1` to divide the lights into sections, or `2` to step through them.
+    #[serde(rename = "f")]
+    pub filter_type: u8,
+    /// The first parameter of the filter, which depends on
+    /// [`IndexFilter::filter_type`].
+    #[serde(rename = "p")]
+    pub parameter_0: i32,
+    /// The second parameter of the filter, which depends on
+    /// [`IndexFilter::filter_type`].
+    #[serde(rename = "t")]
+    pub parameter_1: i32,
+    /// `1` to reverse the order of the lights, `0` otherwise.
+    #[serde(rename = "r")]
+    pub reverse: u8,
+    /// How the order of the lights is randomized.
+    #[serde(rename = "n")]
+    pub random_type: u8,
+    /// The seed used to randomize the order of the lights.
+    #[serde(rename = "s")]
+    pub seed: i32,
+    /// The portion of the filtered lights to use, from 0.0 to 1.0.
+    #[serde(rename = "l")]
+    pub limit: f64,
+    /// What [`IndexFilter::limit`] also applies to.
+    #[serde(rename = "d")]
+    pub limit_affects_type: u8,
+}
+
+/// Describes how the events of an [`EventBox`] controlling color are spread
+/// across lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LightColorEventBox {
+    /// How far apart (in beats) events are spread across lights.
+    #[serde(rename = "w")]
+    pub beat_distribution: f64,
+    /// `1` to spread events over the whole distribution, or `2` to space
+    /// them by it.
+    #[serde(rename = "d")]
+    pub beat_distribution_type: u8,
+    /// How much brightness is spread across lights.
+    #[serde(rename = "s")]
+    pub brightness_distribution: f64,
+    /// Like [`LightColorEventBox::beat_distribution_type`], for brightness.
+    #[serde(rename = "t")]
+    pub brightness_distribution_type: u8,
+    /// `1` if the brightness distribution affects the first event, `0`
+    /// otherwise.
+    #[serde(rename = "b")]
+    pub brightness_distribution_affects_first: u8,
+    /// The easing applied to the distribution.
+    #[serde(rename = "e")]
+    pub easing: i32,
+}
+
+/// A change in the color of lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LightColorEvent {
+    /// How the lights transition from the previous event.
+    #[serde(rename = "p")]
+    pub transition_type: u8,
+    /// The easing of the transition.
+    #[serde(rename = "e")]
+    pub easing: i32,
+    /// `0` for the first color of the color scheme, `1` for the second, or
+    /// `2` for white.
+    #[serde(rename = "c")]
+    pub color: u8,
+    /// The brightness of the lights.
+    #[serde(rename = "b")]
+    pub brightness: f64,
+    /// How many times per beat the lights strobe, or `0` to not strobe.
+    #[serde(rename = "f")]
+    pub strobe_frequency: i32,
+    /// The brightness of the lights while strobing.
+    #[serde(rename = "sb")]
+    pub strobe_brightness: f64,
+    /// `1` to fade between strobes, `0` otherwise.
+    #[serde(rename = "sf")]
+    pub strobe_fade: u8,
+}
+
+/// Describes how the events of an [`EventBox`] controlling rotation are spread
+/// across lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LightRotationEventBox {
+    /// See [`LightColorEventBox::beat_distribution`].
+    #[serde(rename = "w")]
+    pub beat_distribution: f64,
+    /// See [`LightColorEventBox::beat_distribution_type`].
+    #[serde(rename = "d")]
+    pub beat_distribution_type: u8,
+    /// How much rotation (in degrees) is spread across lights.
+    #[serde(rename = "s")]
+    pub rotation_distribution: f64,
+    /// Like [`LightColorEventBox::beat_distribution_type`], for rotation.
+    #[serde(rename = "t")]
+    pub rotation_distribution_type: u8,
+    /// `1` if the rotation distribution affects the first event, `0`
+    /// otherwise.
+    #[serde(rename = "b")]
+    pub rotation_distribution_affects_first: u8,
+    /// The easing applied to the distribution.
+    #[serde(rename = "e")]
+    pub easing: i32,
+    /// The axis to rotate around, `0` for x, `1` for y, or `2` for z.
+    #[serde(rename = "a")]
+    pub axis: u8,
+    /// `1` to flip the direction of rotation, `0` otherwise.
+    #[serde(rename = "f")]
+    pub flip: u8,
+}
+
+/// A change in the rotation of lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LightRotationEvent {
+    /// How the lights transition from the previous event.
+    #[serde(rename = "p")]
+    pub transition_type: u8,
+    /// The easing of the transition.
+    #[serde(rename = "e")]
+    pub easing: i32,
+    /// The rotation (in degrees).
+    #[serde(rename = "r")]
+    pub rotation: f64,
+    /// `0` to rotate the shortest way, `1` for clockwise, or `2` for
+    /// counter-clockwise.
+    #[serde(rename = "d")]
+    pub direction: u8,
+    /// How many extra full rotations to make.
+    #[serde(rename = "l")]
+    pub loops: i32,
+}
+
+/// Describes how the events of an [`EventBox`] controlling translation are
+/// spread across lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LightTranslationEventBox {
+    /// See [`LightColorEventBox::beat_distribution`].
+    #[serde(rename = "w")]
+    pub beat_distribution: f64,
+    /// See [`LightColorEventBox::beat_distribution_type`].
+    #[serde(rename = "d")]
+    pub beat_distribution_type: u8,
+    /// How much translation is spread across lights.
+    #[serde(rename = "s")]
+    pub translation_distribution: f64,
+    /// Like [`LightColorEventBox::beat_distribution_type`], for translation.
+    #[serde(rename = "t")]
+    pub translation_distribution_type: u8,
+    /// `1` if the translation distribution affects the first event, `0`
+    /// otherwise.
+    #[serde(rename = "b")]
+    pub translation_distribution_affects_first: u8,
+    /// The easing applied to the distribution.
+    #[serde(rename = "e")]
+    pub easing: i32,
+    /// The axis to translate along, `0` for x, `1` for y, or `2` for z.
+    #[serde(rename = "a")]
+    pub axis: u8,
+    /// `1` to flip the direction of translation, `0` otherwise.
+    #[serde(rename = "f")]
+    pub flip: u8,
+}
+
+/// A change in the position of lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LightTranslationEvent {
+    /// How the lights transition from the previous event.
+    #[serde(rename = "p")]
+    pub transition_type: u8,
+    /// The easing of the transition.
+    #[serde(rename = "e")]
+    pub easing: i32,
+    /// The translation.
+    #[serde(rename = "t")]
+    pub translation: f64,
+}
+
+/// Describes how the events of an [`EventBox`] controlling FX are spread
+/// across lights.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FxEventBox {
+    /// See [`LightColorEventBox::beat_distribution`].
+    #[serde(rename = "w")]
+    pub beat_distribution: f64,
+    /// See [`LightColorEventBox::beat_distribution_type`].
+    #[serde(rename = "d")]
+    pub beat_distribution_type: u8,
+    /// How much of the FX value is spread across lights.
+    #[serde(rename = "s")]
+    pub fx_distribution: f64,
+    /// Like [`LightColorEventBox::beat_distribution_type`], for FX.
+    #[serde(rename = "t")]
+    pub fx_distribution_type: u8,
+    /// `1` if the FX distribution affects the first event, `0` otherwise.
+    #[serde(rename = "b")]
+    pub fx_distribution_affects_first: u8,
+    /// The easing applied to the distribution.
+    #[serde(rename = "e")]
+    pub easing: i32,
+}
+
+/// A change in an FX value of the environment.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FloatFxEvent {
+    /// How the value transitions from the previous event.
+    #[serde(rename = "p")]
+    pub transition_type: u8,
+    /// The easing of the transition.
+    #[serde(rename = "e")]
+    pub easing: i32,
+    /// The value.
+    #[serde(rename = "v")]
+    pub value: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::beatmap::{LineIndex, LineLayer};
+
+    use super::*;
+
+    fn sample() -> String {
+        fs::read_to_string("sample/Lightshow.dat").unwrap()
+    }
+
+    fn manual_recreation() -> Lightshow {
+        let event = Event {
+            beat: 10.0,
+            metadata_index: 0,
+        };
+        let box_events = |group_type| EventBoxGroup {
+            beat: 2.0,
+            group_id: 0,
+            group_type,
+            event_boxes: vec![EventBox {
+                index_filter: 0,
+                event_box: 0,
+                events: vec![Event {
+                    beat: 0.0,
+                    metadata_index: 0,
+                }],
+            }],
+        };
+
+        Lightshow {
+            version: "4.0.0".to_string(),
+            basic_events: vec![event.clone()],
+            basic_events_data: vec![BasicEventData {
+                event_type: 1,
+                value: 3,
+                float_value: 1.0,
+            }],
+            color_boost_events: vec![event.clone()],
+            color_boost_events_data: vec![ColorBoostEventData { boost: 1 }],
+            waypoints: vec![event],
+            waypoints_data: vec![WaypointData {
+                grid_position: GridPosition {
+                    line_index: LineIndex::Left,
+                    line_layer: LineLayer::Bottom,
+                },
+                offset_direction: CutDirection::Down,
+            }],
+            basic_event_types_with_keywords: BasicEventTypesWithKeywords {
+                keywords: vec![EventTypesForKeyword {
+                    keyword: "SECRET".to_string(),
+                    event_types: vec![40, 41, 42, 43],
+                }],
+            },
+            event_box_groups: (1..=4).map(box_events).collect(),
+            index_filters: vec![IndexFilter {
+                chunks: 1,
+                filter_type: 1,
+                parameter_0: 1,
+                parameter_1: 0,
+                reverse: 0,
+                random_type: 0,
+                seed: 0,
+                limit: 0.0,
+                limit_affects_type: 0,
+            }],
+            light_color_event_boxes: vec![LightColorEventBox {
+                beat_distribution: 1.0,
+                beat_distribution_type: 1,
+                brightness_distribution: 1.0,
+                brightness_distribution_type: 1,
+                brightness_distribution_affects_first: 1,
+                easing: 0,
+            }],
+            light_color_events: vec![LightColorEvent {
+                transition_type: 0,
+                easing: -1,
+                color: 1,
+                brightness: 1.0,
+                strobe_frequency: 0,
+                strobe_brightness: 0.0,
+                strobe_fade: 0,
+            }],
+            light_rotation_event_boxes: vec![LightRotationEventBox {
+                beat_distribution: 1.0,
+                beat_distribution_type: 1,
+                rotation_distribution: 1.0,
+                rotation_distribution_type: 1,
+                rotation_distribution_affects_first: 1,
+                easing: 0,
+                axis: 0,
+                flip: 1,
+            }],
+            light_rotation_events: vec![LightRotationEvent {
+                transition_type: 0,
+                easing: 1,
+                rotation: 340.0,
+                direction: 1,
+                loops: 1,
+            }],
+            light_translation_event_boxes: vec![LightTranslationEventBox {
+                beat_distribution: 1.0,
+                beat_distribution_type: 1,
+                translation_distribution: 1.0,
+                translation_distribution_type: 1,
+                translation_distribution_affects_first: 1,
+                easing: 0,
+                axis: 0,
+                flip: 1,
+            }],
+            light_translation_events: vec![LightTranslationEvent {
+                transition_type: 0,
+                easing: 1,
+                translation: 100.0,
+            }],
+            fx_event_boxes: vec![FxEventBox {
+                beat_distribution: 1.0,
+                beat_distribution_type: 1,
+                fx_distribution: 1.0,
+                fx_distribution_type: 1,
+                fx_distribution_affects_first: 1,
+                easing: 0,
+            }],
+            float_fx_events: vec![FloatFxEvent {
+                transition_type: 0,
+                easing: 1,
+                value: 100.0,
+            }],
+            use_normal_events_as_compatible_events: false,
+        }
+    }
+
+    #[test]
+    fn deserializes_correctly() {
+        let deserialized: Lightshow = serde_json::from_str(&sample()).unwrap();
+
+        assert_eq!(deserialized, manual_recreation());
+    }
+
+    #[test]
+    fn round_trips_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Lightshow.dat");
+
+        manual_recreation().to_file(&path).unwrap();
+
+        assert_eq!(Lightshow::from_file(&path).unwrap(), manual_recreation());
+    }
+}
@@ -0,0 +1,132 @@
+//! Contains [`Ordered`], for re-saving map files without changing the order of
+//! their keys.
+//!
+//! See [`Ordered`] to get started.
+
+use std::{fs, path::Path, str::FromStr};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::Error;
+
+/// A map file along with the order its JSON keys were originally written in.
+///
+/// Normally, keys are serialized in the order fields are declared in this
+/// crate, which may differ from the order of the original file and changes its
+/// bytes (and its hash) even if its contents were not modified. [`Ordered`]
+/// remembers the original order and reproduces it when serialized. Keys which
+/// did not exist in the original file are written after those which did.
+///
+/// Only the order of keys is preserved, whitespace and number formatting are
+/// not.
+///
+/// # Examples
+///
+/// ```
+/// use beat_saber_map::{ordered::Ordered, Audio};
+///
+/// let json = r#"{"songFrequency":44100,"version":"4.0.0"}"#;
+/// let mut audio: Ordered<Audio> = json.parse().unwrap();
+///
+/// audio.value.song_frequency = 48000;
+///
+/// assert!(audio.to_string().unwrap().starts_with(r#"{"songFrequency":48000,"version":"4.0.0""#));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ordered<T> {
+    /// The deserialized map file.
+    pub value: T,
+    layout: Value,
+}
+
+impl<T: DeserializeOwned + Serialize> Ordered<T> {
+    /// Instantiates an [`Ordered`] from a map file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Serializes the map file as compact JSON in its original key order.
+    pub fn to_string(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self.ordered_value()?)?)
+    }
+
+    /// Serializes the map file as pretty-printed JSON in its original key
+    /// order.
+    pub fn to_string_pretty(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self.ordered_value()?)?)
+    }
+
+    fn ordered_value(&self) -> Result<Value, Error> {
+        Ok(reorder(serde_json::to_value(&self.value)?, &self.layout))
+    }
+}
+
+impl<T: DeserializeOwned> FromStr for Ordered<T> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let layout: Value = serde_json::from_str(s)?;
+
+        Ok(Self {
+            value: T::deserialize(&layout)?,
+            layout,
+        })
+    }
+}
+
+/// Recursively sorts the keys of `value` to match the order of `layout`.
+///
+/// Elements of arrays are matched with the element at the same index in
+/// `layout`, or its last element if `layout` is shorter.
+fn reorder(value: Value, layout: &Value) -> Value {
+    match (value, layout) {
+        (Value::Object(mut map), Value::Object(layout)) => {
+            let mut ordered = Map::new();
+
+            for (key, layout) in layout {
+                if let Some(value) = map.shift_remove(key) {
+                    ordered.insert(key.clone(), reorder(value, layout));
+                }
+            }
+
+            ordered.extend(map);
+
+            Value::Object(ordered)
+        }
+        (Value::Array(values), Value::Array(layout)) => Value::Array(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| match layout.get(i).or(layout.last()) {
+                    Some(layout) => reorder(value, layout),
+                    None => value,
+                })
+                .collect(),
+        ),
+        (value, _) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::Beatmap;
+
+    #[test]
+    fn preserves_key_order() {
+        let sample: Map<String, Value> =
+            serde_json::from_str(&fs::read_to_string("sample/Normal.dat").unwrap()).unwrap();
+        let reversed: Map<String, Value> = sample.into_iter().rev().collect();
+        let reversed = serde_json::to_string_pretty(&reversed).unwrap();
+        let ordered: Ordered<Beatmap> = reversed.parse().unwrap();
+
+        assert_ne!(
+            serde_json::to_string_pretty(&ordered.value).unwrap(),
+            reversed
+        );
+        assert_eq!(ordered.to_string_pretty().unwrap(), reversed);
+    }
+}
@@ -0,0 +1,182 @@
+//! Contains [`MapSource`], for loading maps from places other than a folder.
+//!
+//! See [`MapSource`] to get started.
+
+#[cfg(feature = "gzip")]
+use std::fs;
+#[cfg(any(feature = "gzip", feature = "zip"))]
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "zip")]
+use std::{
+    cell::RefCell,
+    io::{Read, Seek},
+};
+
+#[cfg(feature = "gzip")]
+use flate2::read::GzDecoder;
+#[cfg(feature = "zip")]
+use zip::ZipArchive;
+
+#[cfg(any(feature = "gzip", feature = "zip"))]
+use crate::read_limited;
+use crate::{read_file, Error, LoadOptions};
+
+/// Somewhere the files of a map can be read from, such as a map folder.
+///
+/// Implement this to load maps from custom locations with
+/// [`crate::BeatSaberMap::from_source`].
+///
+/// # Examples
+///
+/// ```
+/// use std::{collections::HashMap, io};
+///
+/// use beat_saber_map::{source::MapSource, BeatSaberMap, Error};
+///
+/// struct Embedded(HashMap<&'static str, &'static [u8]>);
+///
+/// impl MapSource for Embedded {
+///     fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+///         self.0
+///             .get(name)
+///             .map(|bytes| bytes.to_vec())
+///             .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound).into())
+///     }
+/// }
+///
+/// let source = Embedded(HashMap::from([
+///     ("Info.dat", &br#"{"version":"4.0.0"}"#[..]),
+///     ("BPMInfo.dat", &br#"{"version":"4.0.0"}"#[..]),
+/// ]));
+///
+/// assert_eq!(BeatSaberMap::from_source(source).unwrap(), BeatSaberMap::default());
+/// ```
+pub trait MapSource {
+    /// Reads the entire contents of the file called `name`.
+    ///
+    /// If the file does not exist, this should return [`Error::Io`] with an
+    /// [`std::io::ErrorKind::NotFound`] error, so that missing files can be
+    /// reported as [`Error::FileNotFound`].
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error>;
+}
+
+impl<T: MapSource + ?Sized> MapSource for &T {
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        (**self).read(name)
+    }
+}
+
+/// Reads files from a map folder, limiting their size as set by
+/// [`LoadOptions`].
+///
+/// With the `gzip` feature enabled, a file that does not exist is read from a
+/// gzip-compressed copy with a `.gz` suffix instead, if there is one.
+#[derive(Debug, Clone, Copy)]
+pub struct Dir<'a> {
+    path: &'a Path,
+    options: &'a LoadOptions,
+}
+
+impl<'a> Dir<'a> {
+    /// Reads files from the map folder at `path` with the given `options`.
+    pub fn new(path: &'a Path, options: &'a LoadOptions) -> Self {
+        Self { path, options }
+    }
+}
+
+impl MapSource for Dir<'_> {
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let path = self.path.join(name);
+
+        match read_file(&path, self.options) {
+            #[cfg(feature = "gzip")]
+            Err(Error::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                let mut gz_path = path.clone().into_os_string();
+
+                gz_path.push(".gz");
+
+                let Ok(file) = fs::File::open(gz_path) else {
+                    return Err(err.into());
+                };
+
+                read_limited(GzDecoder::new(file), &path, self.options)
+            }
+            result => result,
+        }
+    }
+}
+
+/// Reads files from a map folder, with the default [`LoadOptions`].
+///
+/// See [`Dir`].
+impl MapSource for Path {
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        Dir::new(self, &LoadOptions::default()).read(name)
+    }
+}
+
+/// Reads files from a map folder.
+impl MapSource for PathBuf {
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        self.as_path().read(name)
+    }
+}
+
+/// Reads files from a zip archive, such as a map downloaded from BeatSaver.
+///
+/// Files are looked up case-insensitively, since archives are not consistent
+/// about names like `Info.dat` and `info.dat`. Their size is limited as set by
+/// [`LoadOptions`], both as recorded in the archive and as decompressed.
+#[cfg(feature = "zip")]
+#[derive(Debug)]
+pub struct ZipSource<R> {
+    archive: RefCell<ZipArchive<R>>,
+    options: LoadOptions,
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + Seek> ZipSource<R> {
+    /// Opens the zip archive read by `reader`, with the default
+    /// [`LoadOptions`].
+    pub fn new(reader: R) -> Result<Self, Error> {
+        Self::with_options(reader, &LoadOptions::default())
+    }
+
+    /// Opens the zip archive read by `reader`, with the given `options`.
+    pub fn with_options(reader: R, options: &LoadOptions) -> Result<Self, Error> {
+        Ok(Self {
+            archive: RefCell::new(ZipArchive::new(reader)?),
+            options: options.clone(),
+        })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + Seek> MapSource for ZipSource<R> {
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut archive = self.archive.borrow_mut();
+        let index = (0..archive.len())
+            .find(|&index| {
+                archive
+                    .name_for_index(index)
+                    .is_some_and(|entry| entry.eq_ignore_ascii_case(name))
+            })
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let file = archive.by_index(index)?;
+        let path = Path::new(name);
+
+        if let Some(max_file_size) = self.options.max_file_size {
+            if file.size() > max_file_size {
+                return Err(Error::FileTooLarge {
+                    path: path.to_path_buf(),
+                    max_file_size,
+                });
+            }
+        }
+
+        // The recorded size may be wrong, so the decompressed size is limited
+        // too.
+        read_limited(file, path, &self.options)
+    }
+}
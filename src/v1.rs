@@ -0,0 +1,474 @@
+//! Contains types for reading v1 map files, the format of the earliest custom
+//! maps, so that they can be converted to the current format.
+//!
+//! v1 maps consist of an `info.json` file, see [`InfoV1`], and a JSON file per
+//! difficulty, see [`BeatmapV1`]. Conversion is best-effort: anything with no
+//! equivalent in the current format is dropped, and can be listed beforehand
+//! with [`InfoV1::dropped_fields`] and [`BeatmapV1::dropped_fields`].
+//!
+//! v2 beatmaps lay out notes and obstacles the same way, so [`BeatmapV1`] is
+//! also how [`Beatmap::from_file`] and [`Beatmap::from_slice`] convert them.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    beatmap::{
+        Color, ColorNoteData, CutDirection, GridPosition, LineIndex, LineLayer, Object,
+        ObstacleData,
+    },
+    info::{self, Characteristic, Difficulty, DifficultyBeatmap, Song},
+    parse_file, Beatmap, Beats, Error, Info,
+};
+
+/// A v1 `info.json` file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct InfoV1 {
+    /// The title of the song.
+    pub song_name: String,
+    /// The subtitle of the song.
+    pub song_sub_name: String,
+    /// The artist of the song.
+    pub author_name: String,
+    /// The BPM of the song.
+    pub beats_per_minute: Beats,
+    /// The time (in seconds) of the song to start the preview at.
+    pub preview_start_time: f64,
+    /// The duration (in seconds) of the preview.
+    pub preview_duration: f64,
+    /// The cover image file.
+    pub cover_image_path: PathBuf,
+    /// The environment all difficulties are played in.
+    pub environment_name: String,
+    /// See [`DifficultyLevelV1`].
+    pub difficulty_levels: Vec<DifficultyLevelV1>,
+}
+
+impl InfoV1 {
+    /// Instantiates an [`InfoV1`] from a v1 info file, typically named
+    /// `info.json`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        parse_file(path.as_ref(), |bytes| Ok(serde_json::from_slice(bytes)?))
+    }
+
+    /// The fields holding information that converting to [`Info`] will drop.
+    ///
+    /// Every difficulty may have its own audio file in v1, but the current
+    /// format has one per map, so only the first is kept. Audio offsets have
+    /// no equivalent at all.
+    pub fn dropped_fields(&self) -> Vec<&'static str> {
+        let mut dropped = Vec::new();
+        let first_audio_path = self
+            .difficulty_levels
+            .first()
+            .map(|level| &level.audio_path);
+
+        if self
+            .difficulty_levels
+            .iter()
+            .any(|level| Some(&level.audio_path) != first_audio_path)
+        {
+            dropped.push("difficultyLevels.audioPath");
+        }
+
+        if self
+            .difficulty_levels
+            .iter()
+            .any(|level| level.offset != 0.0)
+        {
+            dropped.push("difficultyLevels.offset");
+        }
+
+        dropped
+    }
+}
+
+/// Converts the metadata of a v1 map.
+///
+/// Every difficulty is given the [`Characteristic::Standard`] characteristic,
+/// and its beatmap filename is the v1 `jsonPath` with a `.dat` extension.
+/// Note jump speeds are stored in v1 beatmaps instead, see
+/// [`BeatmapV1::note_jump_speed`].
+impl From<InfoV1> for Info {
+    fn from(value: InfoV1) -> Self {
+        Self {
+            song: Song {
+                title: value.song_name,
+                subtitle: value.song_sub_name,
+                author: value.author_name,
+            },
+            audio: info::Audio {
+                song_filename: value
+                    .difficulty_levels
+                    .first()
+                    .map(|level| level.audio_path.clone())
+                    .unwrap_or_default(),
+                bpm: value.beats_per_minute,
+                preview_start_time: value.preview_start_time,
+                preview_duration: value.preview_duration,
+                ..Default::default()
+            },
+            cover_image_filename: value.cover_image_path,
+            environment_names: vec![value.environment_name],
+            difficulty_beatmaps: value
+                .difficulty_levels
+                .into_iter()
+                .map(|level| DifficultyBeatmap {
+                    characteristic: Characteristic::Standard,
+                    difficulty: level.difficulty,
+                    beatmap_data_filename: level.json_path.with_extension("dat"),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A difficulty listed in a v1 `info.json` file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct DifficultyLevelV1 {
+    /// See [`Difficulty`].
+    pub difficulty: Difficulty,
+    /// The rank of the difficulty, from 1 for easy to 5 for expert+.
+    pub difficulty_rank: u8,
+    /// The audio file the difficulty is played to.
+    pub audio_path: PathBuf,
+    /// The beatmap file of the difficulty.
+    pub json_path: PathBuf,
+    /// The offset (in milliseconds) of the audio file.
+    pub offset: f64,
+}
+
+/// A v1 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct BeatmapV1 {
+    /// The BPM of the song.
+    #[serde(rename = "_beatsPerMinute")]
+    pub beats_per_minute: Beats,
+    /// The number of beats in a bar, which only affected the editor.
+    #[serde(rename = "_beatsPerBar")]
+    pub beats_per_bar: u32,
+    /// The speed at which objects move towards the player, which belongs in
+    /// [`DifficultyBeatmap::note_jump_movement_speed`] in the current format.
+    #[serde(rename = "_noteJumpSpeed")]
+    pub note_jump_speed: u32,
+    /// How much to randomize lighting events by.
+    #[serde(rename = "_shuffle")]
+    pub shuffle: f64,
+    /// How often to randomize lighting events.
+    #[serde(rename = "_shufflePeriod")]
+    pub shuffle_period: f64,
+    /// See [`NoteV1`].
+    #[serde(rename = "_notes")]
+    pub notes: Vec<NoteV1>,
+    /// See [`ObstacleV1`].
+    #[serde(rename = "_obstacles")]
+    pub obstacles: Vec<ObstacleV1>,
+    /// Lighting events, which are not converted.
+    #[serde(rename = "_events")]
+    pub events: Vec<serde_json::Value>,
+}
+
+impl BeatmapV1 {
+    /// Instantiates a [`BeatmapV1`] from a v1 beatmap file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        parse_file(path.as_ref(), |bytes| Ok(serde_json::from_slice(bytes)?))
+    }
+
+    /// The fields holding information that converting to [`Beatmap`] will
+    /// drop.
+    ///
+    /// Lighting events and their shuffling are dropped, as are notes and
+    /// obstacles which are not valid in the current format.
+    /// [`BeatmapV1::beats_per_minute`] and [`BeatmapV1::note_jump_speed`] are
+    /// never listed, because they belong in [`Info`], where they should be
+    /// moved by hand.
+    pub fn dropped_fields(&self) -> Vec<&'static str> {
+        let mut dropped = Vec::new();
+
+        if self.shuffle != 0.0 || self.shuffle_period != 0.0 {
+            dropped.push("_shuffle");
+        }
+
+        if !self.events.is_empty() {
+            dropped.push("_events");
+        }
+
+        if self.notes.iter().any(|note| {
+            note.to_grid_position().is_none()
+                || note.to_cut_direction().is_none()
+                || ![0, 1, 3].contains(&note.kind)
+        }) {
+            dropped.push("_notes");
+        }
+
+        if self.obstacles.iter().any(|obstacle| {
+            obstacle.to_line_index_and_width().is_none() || ![0, 1].contains(&obstacle.kind)
+        }) {
+            dropped.push("_obstacles");
+        }
+
+        dropped
+    }
+}
+
+/// Converts the objects of a v1 beatmap.
+///
+/// Notes and obstacles which are not valid in the current format are skipped.
+impl From<BeatmapV1> for Beatmap {
+    fn from(value: BeatmapV1) -> Self {
+        let bombs = value.notes.iter().filter(|note| note.kind == 3).count();
+        let mut beatmap = Beatmap::with_capacities(
+            value.notes.len() - bombs,
+            bombs,
+            value.obstacles.len(),
+            0,
+            0,
+        );
+
+        for note in &value.notes {
+            let (Some(grid_position), Some(cut_direction)) =
+                (note.to_grid_position(), note.to_cut_direction())
+            else {
+                continue;
+            };
+            let color = match note.kind {
+                0 => Color::LeftSaber,
+                1 => Color::RightSaber,
+                3 => {
+                    beatmap.bomb_notes.push(Object {
+                        beat: note.time,
+                        metadata_index: beatmap.bomb_notes_data.len(),
+                        ..Default::default()
+                    });
+                    beatmap.bomb_notes_data.push(grid_position);
+
+                    continue;
+                }
+                _ => continue,
+            };
+
+            beatmap.color_notes.push(Object {
+                beat: note.time,
+                metadata_index: beatmap.color_notes_data.len(),
+                ..Default::default()
+            });
+            beatmap.color_notes_data.push(ColorNoteData {
+                grid_position,
+                color,
+                cut_direction,
+                ..Default::default()
+            });
+        }
+
+        for obstacle in &value.obstacles {
+            let Some((line_index, width)) = obstacle.to_line_index_and_width() else {
+                continue;
+            };
+            let (line_layer, height) = match obstacle.kind {
+                0 => (LineLayer::Bottom, 5),
+                1 => (LineLayer::Top, 3),
+                _ => continue,
+            };
+
+            beatmap.obstacles.push(Object {
+                beat: obstacle.time,
+                metadata_index: beatmap.obstacles_data.len(),
+                ..Default::default()
+            });
+            beatmap.obstacles_data.push(ObstacleData {
+                duration: obstacle.duration,
+                grid_position: GridPosition {
+                    line_index,
+                    line_layer,
+                },
+                width,
+                height,
+                ..Default::default()
+            });
+        }
+
+        beatmap
+            .compact()
+            .expect("indices should have been created in bounds");
+
+        beatmap
+    }
+}
+
+/// A note or bomb in a v1 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct NoteV1 {
+    /// The beat the note reaches the player on.
+    #[serde(rename = "_time")]
+    pub time: Beats,
+    /// The column of the note, see [`LineIndex`].
+    #[serde(rename = "_lineIndex")]
+    pub line_index: i32,
+    /// The row of the note, see [`LineLayer`].
+    #[serde(rename = "_lineLayer")]
+    pub line_layer: i32,
+    /// `0` for a left note, `1` for a right note, or `3` for a bomb.
+    #[serde(rename = "_type")]
+    pub kind: u8,
+    /// See [`CutDirection`].
+    #[serde(rename = "_cutDirection")]
+    pub cut_direction: i32,
+}
+
+impl NoteV1 {
+    fn to_grid_position(&self) -> Option<GridPosition> {
+        Some(GridPosition {
+            line_index: line_index(self.line_index)?,
+            line_layer: u8::try_from(self.line_layer).ok()?.try_into().ok()?,
+        })
+    }
+
+    fn to_cut_direction(&self) -> Option<CutDirection> {
+        u8::try_from(self.cut_direction).ok()?.try_into().ok()
+    }
+}
+
+/// Converts a column, if it's on the grid.
+///
+/// Fields which Mapping Extensions may place off of the grid are deserialized
+/// as [`i32`], so that such objects can be skipped rather than failing the
+/// whole file.
+fn line_index(value: i32) -> Option<LineIndex> {
+    u8::try_from(value).ok()?.try_into().ok()
+}
+
+/// An obstacle in a v1 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct ObstacleV1 {
+    /// The beat the obstacle reaches the player on.
+    #[serde(rename = "_time")]
+    pub time: Beats,
+    /// The leftmost column of the obstacle, see [`LineIndex`].
+    #[serde(rename = "_lineIndex")]
+    pub line_index: i32,
+    /// `0` for a full height wall, or `1` for a crouch wall.
+    #[serde(rename = "_type")]
+    pub kind: i32,
+    /// How long the obstacle extends for.
+    #[serde(rename = "_duration")]
+    pub duration: Beats,
+    /// How many columns the obstacle takes up.
+    #[serde(rename = "_width")]
+    pub width: i32,
+}
+
+impl ObstacleV1 {
+    fn to_line_index_and_width(&self) -> Option<(LineIndex, i8)> {
+        Some((line_index(self.line_index)?, self.width.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_info() {
+        let json = r#"{
+            "songName": "Magic",
+            "songSubName": "",
+            "authorName": "Jaroslav Beck",
+            "beatsPerMinute": 208,
+            "previewStartTime": 12,
+            "previewDuration": 10,
+            "coverImagePath": "cover.jpg",
+            "environmentName": "DefaultEnvironment",
+            "difficultyLevels": [
+                { "difficulty": "Expert", "difficultyRank": 4, "audioPath": "song.ogg", "jsonPath": "Expert.json", "offset": 0 },
+                { "difficulty": "Easy", "difficultyRank": 1, "audioPath": "song_easy.ogg", "jsonPath": "Easy.json", "offset": 0 }
+            ]
+        }"#;
+        let info_v1: InfoV1 = serde_json::from_str(json).unwrap();
+
+        assert_eq!(info_v1.dropped_fields(), ["difficultyLevels.audioPath"]);
+
+        let info = Info::from(info_v1);
+
+        assert_eq!(info.song.author, "Jaroslav Beck");
+        assert_eq!(info.audio.song_filename, Path::new("song.ogg"));
+        assert_eq!(info.audio.bpm, 208.0);
+        assert_eq!(info.environment_names, ["DefaultEnvironment"]);
+        assert_eq!(info.difficulty_beatmaps[0].difficulty, Difficulty::Expert);
+        assert_eq!(
+            info.difficulty_beatmaps[1].beatmap_data_filename,
+            Path::new("Easy.dat")
+        );
+    }
+
+    #[test]
+    fn converts_beatmap() {
+        let json = r#"{
+            "_version": "1.5.0",
+            "_beatsPerMinute": 208,
+            "_beatsPerBar": 16,
+            "_noteJumpSpeed": 10,
+            "_shuffle": 0,
+            "_shufflePeriod": 0.5,
+            "_events": [{ "_time": 0, "_type": 1, "_value": 3 }],
+            "_notes": [
+                { "_time": 4, "_lineIndex": 1, "_lineLayer": 0, "_type": 0, "_cutDirection": 1 },
+                { "_time": 5, "_lineIndex": 2, "_lineLayer": 0, "_type": 1, "_cutDirection": 1 },
+                { "_time": 6, "_lineIndex": 3, "_lineLayer": 2, "_type": 3, "_cutDirection": 0 },
+                { "_time": 7, "_lineIndex": 9, "_lineLayer": 0, "_type": 0, "_cutDirection": 1 }
+            ],
+            "_obstacles": [
+                { "_time": 8, "_lineIndex": 0, "_type": 1, "_duration": 2, "_width": 4 }
+            ]
+        }"#;
+        let beatmap_v1: BeatmapV1 = serde_json::from_str(json).unwrap();
+
+        assert_eq!(beatmap_v1.note_jump_speed, 10);
+        assert_eq!(
+            beatmap_v1.dropped_fields(),
+            ["_shuffle", "_events", "_notes"]
+        );
+
+        let beatmap = Beatmap::from(beatmap_v1);
+
+        assert_eq!(beatmap.color_notes.len(), 2);
+        assert_eq!(beatmap.color_notes_data.len(), 2);
+        assert_eq!(beatmap.color_notes_data[1].color, Color::RightSaber);
+        assert_eq!(beatmap.bomb_notes[0].beat, 6.0);
+        assert_eq!(beatmap.bomb_notes_data[0].line_layer, LineLayer::Top);
+        assert_eq!(beatmap.obstacles_data[0].height, 3);
+        assert!(beatmap.index_errors().is_empty());
+    }
+
+    #[test]
+    fn skips_mapping_extensions_objects() {
+        let json = r#"{
+            "_version": "2.0.0",
+            "_notes": [
+                { "_time": 4, "_lineIndex": 1, "_lineLayer": 0, "_type": 0, "_cutDirection": 1 },
+                { "_time": 5, "_lineIndex": 2500, "_lineLayer": -1000, "_type": 1, "_cutDirection": 1 },
+                { "_time": 6, "_lineIndex": 2, "_lineLayer": 1, "_type": 1, "_cutDirection": 1180 }
+            ],
+            "_obstacles": [
+                { "_time": 8, "_lineIndex": -2000, "_type": 4001, "_duration": 2, "_width": 1500 }
+            ]
+        }"#;
+        let beatmap_v1: BeatmapV1 = serde_json::from_str(json).unwrap();
+
+        assert_eq!(beatmap_v1.dropped_fields(), ["_notes", "_obstacles"]);
+
+        let beatmap = Beatmap::from_slice(json.as_bytes()).unwrap();
+
+        assert_eq!(beatmap.color_notes.len(), 1);
+        assert!(beatmap.obstacles.is_empty());
+    }
+}
@@ -0,0 +1,559 @@
+//! Contains types for reading v3 beatmap files, so that they can be converted
+//! to the current format.
+//!
+//! v3 beatmaps are mostly the current format without the split between objects
+//! and their data, see [`BeatmapV3`]. They're converted automatically by
+//! [`Beatmap::from_file`] and [`Beatmap::from_slice`]. Conversion is
+//! best-effort: lighting events belong in a separate lightshow file in the
+//! current format and are dropped, as are objects which are not valid in the
+//! current format. Both can be listed beforehand with
+//! [`BeatmapV3::dropped_fields`].
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{
+    beatmap::{
+        Arc, ArcData, Chain, ChainData, Color, ColorNoteData, CutDirection, GridPosition,
+        MidAnchorMode, Object, ObstacleData,
+    },
+    parse_file, Beatmap, Beats, Error,
+};
+
+/// A v3 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct BeatmapV3 {
+    /// The version of the format, such as `"3.3.0"`.
+    pub version: String,
+    /// See [`ColorNoteV3`].
+    pub color_notes: Vec<ColorNoteV3>,
+    /// See [`BombNoteV3`].
+    pub bomb_notes: Vec<BombNoteV3>,
+    /// See [`ObstacleV3`].
+    pub obstacles: Vec<ObstacleV3>,
+    /// Arcs, see [`SliderV3`].
+    pub sliders: Vec<SliderV3>,
+    /// Chains, see [`BurstSliderV3`].
+    pub burst_sliders: Vec<BurstSliderV3>,
+    /// See [`RotationEventV3`].
+    pub rotation_events: Vec<RotationEventV3>,
+    /// BPM changes, which are not converted.
+    pub bpm_events: Vec<serde_json::Value>,
+    /// Lighting events, which are not converted.
+    pub basic_beatmap_events: Vec<serde_json::Value>,
+    /// Boost lighting events, which are not converted.
+    pub color_boost_beatmap_events: Vec<serde_json::Value>,
+    /// Lighting event box groups, which are not converted.
+    pub light_color_event_box_groups: Vec<serde_json::Value>,
+    /// Lighting event box groups, which are not converted.
+    pub light_rotation_event_box_groups: Vec<serde_json::Value>,
+    /// Lighting event box groups, which are not converted.
+    pub light_translation_event_box_groups: Vec<serde_json::Value>,
+    /// Waypoints, which are not converted.
+    pub waypoints: Vec<serde_json::Value>,
+}
+
+impl BeatmapV3 {
+    /// Instantiates a [`BeatmapV3`] from a v3 beatmap file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        parse_file(path.as_ref(), |bytes| Ok(serde_json::from_slice(bytes)?))
+    }
+
+    /// The fields holding information that converting to [`Beatmap`] will
+    /// drop.
+    ///
+    /// Lighting events and BPM changes are dropped, as are objects which are
+    /// not valid in the current format.
+    pub fn dropped_fields(&self) -> Vec<&'static str> {
+        let mut dropped = Vec::new();
+        let lighting = [
+            ("bpmEvents", &self.bpm_events),
+            ("basicBeatmapEvents", &self.basic_beatmap_events),
+            ("colorBoostBeatmapEvents", &self.color_boost_beatmap_events),
+            (
+                "lightColorEventBoxGroups",
+                &self.light_color_event_box_groups,
+            ),
+            (
+                "lightRotationEventBoxGroups",
+                &self.light_rotation_event_box_groups,
+            ),
+            (
+                "lightTranslationEventBoxGroups",
+                &self.light_translation_event_box_groups,
+            ),
+            ("waypoints", &self.waypoints),
+        ];
+
+        for (field, events) in lighting {
+            if !events.is_empty() {
+                dropped.push(field);
+            }
+        }
+
+        if self
+            .color_notes
+            .iter()
+            .any(|note| note.to_color_note_data().is_none())
+        {
+            dropped.push("colorNotes");
+        }
+
+        if self
+            .bomb_notes
+            .iter()
+            .any(|bomb| grid_position(bomb.x, bomb.y).is_none())
+        {
+            dropped.push("bombNotes");
+        }
+
+        if self
+            .obstacles
+            .iter()
+            .any(|obstacle| grid_position(obstacle.x, obstacle.y).is_none())
+        {
+            dropped.push("obstacles");
+        }
+
+        if self
+            .sliders
+            .iter()
+            .any(|slider| slider.to_color_note_data().is_none())
+        {
+            dropped.push("sliders");
+        }
+
+        if self
+            .burst_sliders
+            .iter()
+            .any(|burst_slider| burst_slider.to_data().is_none())
+        {
+            dropped.push("burstSliders");
+        }
+
+        dropped
+    }
+
+    /// The total rotation (in degrees) of objects on `beat`, from every
+    /// rotation event affecting it.
+    fn rotation_at(&self, beat: Beats) -> i16 {
+        self.rotation_events
+            .iter()
+            .filter(|event| match event.execution_time {
+                0 => event.beat <= beat,
+                _ => event.beat < beat,
+            })
+            .map(|event| event.rotation)
+            .sum::<f64>()
+            .round() as i16
+    }
+}
+
+/// Converts the objects of a v3 beatmap.
+///
+/// Rotation events are applied to the [`Object::rotation_lane`] of the objects
+/// they affect. Objects which are not valid in the current format are skipped.
+impl From<BeatmapV3> for Beatmap {
+    fn from(value: BeatmapV3) -> Self {
+        let mut beatmap = Beatmap::with_capacities(
+            value.color_notes.len(),
+            value.bomb_notes.len(),
+            value.obstacles.len(),
+            value.sliders.len(),
+            value.burst_sliders.len(),
+        );
+
+        // Arcs add data for their head and tail, and chains for their head.
+        beatmap.color_notes_data = Vec::with_capacity(
+            value.color_notes.len() + 2 * value.sliders.len() + value.burst_sliders.len(),
+        );
+
+        for note in &value.color_notes {
+            let Some(data) = note.to_color_note_data() else {
+                continue;
+            };
+
+            beatmap.color_notes.push(Object {
+                beat: note.beat,
+                rotation_lane: value.rotation_at(note.beat),
+                metadata_index: beatmap.color_notes_data.len(),
+                ..Default::default()
+            });
+            beatmap.color_notes_data.push(data);
+        }
+
+        for bomb in &value.bomb_notes {
+            let Some(grid_position) = grid_position(bomb.x, bomb.y) else {
+                continue;
+            };
+
+            beatmap.bomb_notes.push(Object {
+                beat: bomb.beat,
+                rotation_lane: value.rotation_at(bomb.beat),
+                metadata_index: beatmap.bomb_notes_data.len(),
+                ..Default::default()
+            });
+            beatmap.bomb_notes_data.push(grid_position);
+        }
+
+        for obstacle in &value.obstacles {
+            let Some(grid_position) = grid_position(obstacle.x, obstacle.y) else {
+                continue;
+            };
+
+            beatmap.obstacles.push(Object {
+                beat: obstacle.beat,
+                rotation_lane: value.rotation_at(obstacle.beat),
+                metadata_index: beatmap.obstacles_data.len(),
+                ..Default::default()
+            });
+            beatmap.obstacles_data.push(ObstacleData {
+                duration: obstacle.duration,
+                grid_position,
+                width: obstacle.width,
+                height: obstacle.height,
+                ..Default::default()
+            });
+        }
+
+        for slider in &value.sliders {
+            let Some((head, tail, mid_anchor_mode)) = slider.to_color_note_data() else {
+                continue;
+            };
+
+            beatmap.arcs.push(Arc {
+                head_beat: slider.beat,
+                tail_beat: slider.tail_beat,
+                head_rotation_lane: value.rotation_at(slider.beat),
+                tail_rotation_lane: value.rotation_at(slider.tail_beat),
+                head_metadata_index: beatmap.color_notes_data.len(),
+                tail_metadata_index: beatmap.color_notes_data.len() + 1,
+                arc_metadata_index: beatmap.arcs_data.len(),
+            });
+            beatmap.color_notes_data.extend([head, tail]);
+            beatmap.arcs_data.push(ArcData {
+                head_multiplier: slider.head_multiplier,
+                tail_multiplier: slider.tail_multiplier,
+                mid_anchor_mode,
+            });
+        }
+
+        for burst_slider in &value.burst_sliders {
+            let Some((head, data)) = burst_slider.to_data() else {
+                continue;
+            };
+
+            beatmap.chains.push(Chain {
+                head_beat: burst_slider.beat,
+                tail_beat: burst_slider.tail_beat,
+                head_rotation_lane: value.rotation_at(burst_slider.beat),
+                tail_rotation_lane: value.rotation_at(burst_slider.tail_beat),
+                head_metadata_index: beatmap.color_notes_data.len(),
+                chain_metadata_index: beatmap.chains_data.len(),
+            });
+            beatmap.color_notes_data.push(head);
+            beatmap.chains_data.push(data);
+        }
+
+        beatmap
+            .compact()
+            .expect("indices should have been created in bounds");
+
+        beatmap
+    }
+}
+
+/// Converts the column `x` and row `y` of an object, if they are on the grid.
+fn grid_position(x: i32, y: i32) -> Option<GridPosition> {
+    Some(GridPosition {
+        line_index: u8::try_from(x).ok()?.try_into().ok()?,
+        line_layer: u8::try_from(y).ok()?.try_into().ok()?,
+    })
+}
+
+/// Converts the head of an arc or chain, or a color note without an angle
+/// offset, if it's valid in the current format.
+fn color_note_data(x: i32, y: i32, color: u8, cut_direction: i32) -> Option<ColorNoteData> {
+    Some(ColorNoteData {
+        grid_position: grid_position(x, y)?,
+        color: Color::try_from(color).ok()?,
+        cut_direction: CutDirection::try_from(u8::try_from(cut_direction).ok()?).ok()?,
+        ..Default::default()
+    })
+}
+
+/// A color note in a v3 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct ColorNoteV3 {
+    /// The beat the note reaches the player on.
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The column of the note, see [`crate::beatmap::LineIndex`].
+    pub x: i32,
+    /// The row of the note, see [`crate::beatmap::LineLayer`].
+    pub y: i32,
+    /// See [`Color`].
+    #[serde(rename = "c")]
+    pub color: u8,
+    /// See [`CutDirection`].
+    #[serde(rename = "d")]
+    pub cut_direction: i32,
+    /// See [`ColorNoteData::angle_offset`].
+    #[serde(rename = "a")]
+    pub angle_offset: i16,
+}
+
+impl ColorNoteV3 {
+    fn to_color_note_data(&self) -> Option<ColorNoteData> {
+        Some(ColorNoteData {
+            angle_offset: self.angle_offset,
+            ..color_note_data(self.x, self.y, self.color, self.cut_direction)?
+        })
+    }
+}
+
+/// A bomb in a v3 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct BombNoteV3 {
+    /// The beat the bomb reaches the player on.
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The column of the bomb, see [`crate::beatmap::LineIndex`].
+    pub x: i32,
+    /// The row of the bomb, see [`crate::beatmap::LineLayer`].
+    pub y: i32,
+}
+
+/// An obstacle in a v3 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct ObstacleV3 {
+    /// The beat the obstacle reaches the player on.
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The leftmost column of the obstacle, see [`crate::beatmap::LineIndex`].
+    pub x: i32,
+    /// The bottom row of the obstacle, see [`crate::beatmap::LineLayer`].
+    pub y: i32,
+    /// See [`ObstacleData::duration`].
+    #[serde(rename = "d")]
+    pub duration: Beats,
+    /// See [`ObstacleData::width`].
+    #[serde(rename = "w")]
+    pub width: i8,
+    /// See [`ObstacleData::height`].
+    #[serde(rename = "h")]
+    pub height: i8,
+}
+
+/// An arc in a v3 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct SliderV3 {
+    /// See [`Color`].
+    #[serde(rename = "c")]
+    pub color: u8,
+    /// The beat the head of the arc reaches the player on.
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The column of the head, see [`crate::beatmap::LineIndex`].
+    pub x: i32,
+    /// The row of the head, see [`crate::beatmap::LineLayer`].
+    pub y: i32,
+    /// The cut direction of the head, see [`CutDirection`].
+    #[serde(rename = "d")]
+    pub cut_direction: i32,
+    /// See [`ArcData::head_multiplier`].
+    #[serde(rename = "mu")]
+    pub head_multiplier: f64,
+    /// The beat the tail of the arc reaches the player on.
+    #[serde(rename = "tb")]
+    pub tail_beat: Beats,
+    /// The column of the tail, see [`crate::beatmap::LineIndex`].
+    #[serde(rename = "tx")]
+    pub tail_x: i32,
+    /// The row of the tail, see [`crate::beatmap::LineLayer`].
+    #[serde(rename = "ty")]
+    pub tail_y: i32,
+    /// The cut direction of the tail, see [`CutDirection`].
+    #[serde(rename = "tc")]
+    pub tail_cut_direction: i32,
+    /// See [`ArcData::tail_multiplier`].
+    #[serde(rename = "tmu")]
+    pub tail_multiplier: f64,
+    /// See [`MidAnchorMode`].
+    #[serde(rename = "m")]
+    pub mid_anchor_mode: u8,
+}
+
+impl SliderV3 {
+    fn to_color_note_data(&self) -> Option<(ColorNoteData, ColorNoteData, MidAnchorMode)> {
+        Some((
+            color_note_data(self.x, self.y, self.color, self.cut_direction)?,
+            color_note_data(
+                self.tail_x,
+                self.tail_y,
+                self.color,
+                self.tail_cut_direction,
+            )?,
+            self.mid_anchor_mode.try_into().ok()?,
+        ))
+    }
+}
+
+/// A chain in a v3 beatmap file.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct BurstSliderV3 {
+    /// See [`Color`].
+    #[serde(rename = "c")]
+    pub color: u8,
+    /// The beat the head of the chain reaches the player on.
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// The column of the head, see [`crate::beatmap::LineIndex`].
+    pub x: i32,
+    /// The row of the head, see [`crate::beatmap::LineLayer`].
+    pub y: i32,
+    /// The cut direction of the head, see [`CutDirection`].
+    #[serde(rename = "d")]
+    pub cut_direction: i32,
+    /// The beat the tail of the chain reaches the player on.
+    #[serde(rename = "tb")]
+    pub tail_beat: Beats,
+    /// The column of the tail, see [`crate::beatmap::LineIndex`].
+    #[serde(rename = "tx")]
+    pub tail_x: i32,
+    /// The row of the tail, see [`crate::beatmap::LineLayer`].
+    #[serde(rename = "ty")]
+    pub tail_y: i32,
+    /// See [`ChainData::slice_count`].
+    #[serde(rename = "sc")]
+    pub slice_count: u8,
+    /// See [`ChainData::squish_factor`].
+    #[serde(rename = "s")]
+    pub squish_factor: f64,
+}
+
+impl BurstSliderV3 {
+    fn to_data(&self) -> Option<(ColorNoteData, ChainData)> {
+        let tail = grid_position(self.tail_x, self.tail_y)?;
+
+        Some((
+            color_note_data(self.x, self.y, self.color, self.cut_direction)?,
+            ChainData {
+                tail_line_index: tail.line_index,
+                tail_line_layer: tail.line_layer,
+                slice_count: self.slice_count,
+                squish_factor: self.squish_factor,
+            },
+        ))
+    }
+}
+
+/// A lane rotation in a v3 beatmap file, which the current format stores in
+/// [`Object::rotation_lane`] instead.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct RotationEventV3 {
+    /// The beat the rotation happens on.
+    #[serde(rename = "b")]
+    pub beat: Beats,
+    /// `0` to also rotate objects on [`RotationEventV3::beat`], or `1` to only
+    /// rotate objects after it.
+    #[serde(rename = "e")]
+    pub execution_time: u8,
+    /// The rotation (in degrees) to add to the objects it affects.
+    #[serde(rename = "r")]
+    pub rotation: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beatmap::{LineIndex, LineLayer};
+
+    #[test]
+    fn converts_beatmap() {
+        let json = r#"{
+            "version": "3.3.0",
+            "bpmEvents": [{ "b": 0, "m": 120 }],
+            "rotationEvents": [{ "b": 4, "e": 0, "r": 15 }, { "b": 6, "e": 1, "r": -30 }],
+            "colorNotes": [
+                { "b": 4, "x": 1, "y": 0, "c": 0, "d": 1, "a": 45 },
+                { "b": 6, "x": 2, "y": 0, "c": 1, "d": 1, "a": 0 },
+                { "b": 7, "x": 1000, "y": 0, "c": 0, "d": 1, "a": 0 }
+            ],
+            "bombNotes": [{ "b": 2, "x": 3, "y": 2 }],
+            "obstacles": [{ "b": 8, "x": 0, "y": 2, "d": 2, "w": 4, "h": 3 }],
+            "sliders": [
+                {
+                    "c": 1, "b": 6, "x": 2, "y": 0, "d": 1, "mu": 1,
+                    "tb": 8, "tx": 3, "ty": 2, "tc": 0, "tmu": 0.5, "m": 0
+                }
+            ],
+            "burstSliders": [
+                { "c": 0, "b": 4, "x": 1, "y": 0, "d": 1, "tb": 5, "tx": 1, "ty": 2, "sc": 4, "s": 0.5 }
+            ]
+        }"#;
+        let beatmap_v3: BeatmapV3 = serde_json::from_str(json).unwrap();
+
+        assert_eq!(beatmap_v3.dropped_fields(), ["bpmEvents", "colorNotes"]);
+
+        let beatmap = Beatmap::from(beatmap_v3);
+
+        assert_eq!(beatmap.color_notes.len(), 2);
+        assert_eq!(beatmap.color_notes[0].rotation_lane, 15);
+        assert_eq!(beatmap.color_notes[1].rotation_lane, 15);
+        assert_eq!(beatmap.color_notes_data[0].angle_offset, 45);
+        assert_eq!(beatmap.bomb_notes[0].rotation_lane, 0);
+        assert_eq!(beatmap.bomb_notes_data[0].line_layer, LineLayer::Top);
+        assert_eq!(beatmap.obstacles[0].rotation_lane, -15);
+        assert_eq!(beatmap.obstacles_data[0].height, 3);
+
+        let arc = &beatmap.arcs[0];
+
+        assert_eq!(
+            beatmap.color_notes_data[arc.head_metadata_index],
+            beatmap.color_notes_data[beatmap.color_notes[1].metadata_index]
+        );
+        assert_eq!(
+            beatmap.color_notes_data[arc.tail_metadata_index].cut_direction,
+            CutDirection::Up
+        );
+        assert_eq!(beatmap.arcs_data[0].tail_multiplier, 0.5);
+        assert_eq!(beatmap.chains_data[0].tail_line_index, LineIndex::Left);
+        assert_eq!(beatmap.chains_data[0].slice_count, 4);
+        assert!(beatmap.index_errors().is_empty());
+    }
+
+    #[test]
+    fn skips_mapping_extensions_cut_directions() {
+        let json = r#"{
+            "version": "3.3.0",
+            "colorNotes": [
+                { "b": 4, "x": 1, "y": 0, "c": 0, "d": 1, "a": 0 },
+                { "b": 5, "x": 2, "y": 0, "c": 1, "d": 1180, "a": 0 }
+            ],
+            "sliders": [
+                {
+                    "c": 1, "b": 6, "x": 2, "y": 0, "d": 1, "mu": 1,
+                    "tb": 8, "tx": 3, "ty": 2, "tc": 1360, "tmu": 0.5, "m": 0
+                }
+            ],
+            "burstSliders": [
+                { "c": 0, "b": 4, "x": 1, "y": 0, "d": -1, "tb": 5, "tx": 1, "ty": 2, "sc": 4, "s": 0.5 }
+            ]
+        }"#;
+        let beatmap = Beatmap::from_slice(json.as_bytes()).unwrap();
+
+        assert_eq!(beatmap.color_notes.len(), 1);
+        assert!(beatmap.arcs.is_empty());
+        assert!(beatmap.chains.is_empty());
+    }
+}